@@ -1,7 +1,17 @@
-use csv;
+use std::collections::HashMap;
+use std::env;
 use std::error::Error;
-use rand::seq::SliceRandom; 
-use rand::thread_rng;      
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[derive(Debug, Clone)]
 struct Individual {
@@ -14,375 +24,7406 @@ struct Individual {
     family_influence: f64, 
     salary: f64,
     likelihood_to_change_occupation: f64,
+    // Value joined from a --join file by id; NaN when nothing was joined.
+    external: f64,
+    // Set when --impute filled in at least one of this record's values.
+    imputed: bool,
 }
 
-fn read_dataset(file_path: &str) -> Result<Vec<Individual>, Box<dyn Error>> {
-    let mut individuals = Vec::new();
+// Header names of the numeric columns, in the order their values are stored
+// in a RawRow.
+const NUMERIC_COLUMNS: [&str; 6] = [
+    "Age",
+    "Years of Experience",
+    "Job Satisfaction",
+    "Professional Networks",
+    "Salary",
+    "Likely to Change Occupation",
+];
+
+const FAMILY_INFLUENCE_COLUMN: &str = "Family Influence";
+
+// Family Influence labels in ordinal order; each label is encoded as its index.
+const FAMILY_INFLUENCE_LEVELS: [&str; 5] = ["None", "Low", "Medium", "High", "Very High"];
+
+// The label for an encoded family influence value, if it is one.
+fn family_influence_label(value: f64) -> Option<&'static str> {
+    FAMILY_INFLUENCE_LEVELS
+        .iter()
+        .enumerate()
+        .find(|(index, _)| *index as f64 == value)
+        .map(|(_, label)| *label)
+}
+
+// Share of `sample` at each FAMILY_INFLUENCE_LEVELS entry, as fractions.
+fn family_influence_shares(sample: &[Individual]) -> [f64; 5] {
+    let mut shares = [0.0; 5];
+    for (level, share) in shares.iter_mut().enumerate() {
+        let count = sample.iter().filter(|ind| ind.family_influence == level as f64).count();
+        *share = count as f64 / sample.len() as f64;
+    }
+    shares
+}
+
+// Matching ignores case, surrounding whitespace, and whether words are joined
+// by spaces, '_' or '-', so "very_high" and " HIGH " are accepted.
+fn family_influence_value(label: &str) -> Option<f64> {
+    let words: Vec<String> = label
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let normalized = match words.join(" ").as_str() {
+        "med" => "medium".to_string(),
+        "veryhigh" => "very high".to_string(),
+        other => other.to_string(),
+    };
+
+    FAMILY_INFLUENCE_LEVELS
+        .iter()
+        .position(|level| level.to_lowercase() == normalized)
+        .map(|index| index as f64)
+}
+
+// CSV column index of each Individual field. The defaults are the positions
+// in the original career dataset export.
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnConfig {
+    age: usize,
+    years_of_experience: usize,
+    job_satisfaction: usize,
+    professional_network_size: usize,
+    family_influence: usize,
+    salary: usize,
+    likelihood_to_change_occupation: usize,
+}
+
+impl Default for ColumnConfig {
+    fn default() -> Self {
+        ColumnConfig {
+            age: 2,
+            years_of_experience: 4,
+            job_satisfaction: 7,
+            professional_network_size: 19,
+            family_influence: 14,
+            salary: 10,
+            likelihood_to_change_occupation: 22,
+        }
+    }
+}
+
+impl ColumnConfig {
+    // Resolves each field by header name, keeping the default index (with a
+    // warning) for any header that can't be found.
+    fn from_headers(headers: &csv::StringRecord) -> Self {
+        let defaults = ColumnConfig::default();
+        let find = |name: &str, fallback: usize| {
+            headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "Warning: header '{}' not found; using column {}",
+                        name, fallback
+                    );
+                    fallback
+                })
+        };
+
+        ColumnConfig {
+            age: find(NUMERIC_COLUMNS[0], defaults.age),
+            years_of_experience: find(NUMERIC_COLUMNS[1], defaults.years_of_experience),
+            job_satisfaction: find(NUMERIC_COLUMNS[2], defaults.job_satisfaction),
+            professional_network_size: find(NUMERIC_COLUMNS[3], defaults.professional_network_size),
+            family_influence: find(FAMILY_INFLUENCE_COLUMN, defaults.family_influence),
+            salary: find(NUMERIC_COLUMNS[4], defaults.salary),
+            likelihood_to_change_occupation: find(
+                NUMERIC_COLUMNS[5],
+                defaults.likelihood_to_change_occupation,
+            ),
+        }
+    }
+
+    // Checks that every expected header is present, listing each one that
+    // isn't along with what sits at its default position instead. With
+    // `fixed_positions`, a header found at another column is a mismatch too.
+    fn validate_headers(headers: &csv::StringRecord, fixed_positions: bool) -> Result<(), String> {
+        let defaults = ColumnConfig::default();
+        let expected = [
+            (NUMERIC_COLUMNS[0], defaults.age),
+            (NUMERIC_COLUMNS[1], defaults.years_of_experience),
+            (NUMERIC_COLUMNS[2], defaults.job_satisfaction),
+            (NUMERIC_COLUMNS[3], defaults.professional_network_size),
+            (FAMILY_INFLUENCE_COLUMN, defaults.family_influence),
+            (NUMERIC_COLUMNS[4], defaults.salary),
+            (NUMERIC_COLUMNS[5], defaults.likelihood_to_change_occupation),
+        ];
+
+        let mismatches: Vec<String> = expected
+            .iter()
+            .filter_map(|&(name, position)| {
+                match headers.iter().position(|header| header.trim() == name) {
+                    Some(found) if found == position || !fixed_positions => None,
+                    Some(found) => Some(format!("'{}' is column {}, expected column {}", name, found, position)),
+                    None => Some(match headers.get(position) {
+                        Some(found) => format!("'{}' missing (column {} is '{}')", name, position, found),
+                        None => format!("'{}' missing (no column {})", name, position),
+                    }),
+                }
+            })
+            .collect();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Dataset header does not match the expected columns: {} (pass --lenient-columns to read the default positions anyway)",
+                mismatches.join("; ")
+            ))
+        }
+    }
+
+    // Indices of the numeric columns, in NUMERIC_COLUMNS order.
+    fn numeric_indices(&self) -> [usize; 6] {
+        [
+            self.age,
+            self.years_of_experience,
+            self.job_satisfaction,
+            self.professional_network_size,
+            self.salary,
+            self.likelihood_to_change_occupation,
+        ]
+    }
+
+    // Minimum record length needed to read every configured column.
+    fn required_len(&self) -> usize {
+        self.numeric_indices()
+            .into_iter()
+            .chain([self.family_influence])
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+}
+
+// (record index, family influence, numeric values). A None value is a cell
+// that failed to parse and is waiting to be imputed.
+type RawRow = (usize, f64, [Option<f64>; 6]);
+
+#[derive(Debug, Clone)]
+struct ReadOptions {
+    // Fill unparsable numeric cells with the column mean instead of dropping the row.
+    impute: bool,
+    delimiter: u8,
+    // Stop after this many data records.
+    max_records: usize,
+    // Print a diagnostic for every rejected record, not just the total.
+    verbose: bool,
+    // Inclusive bounds for Job Satisfaction; the scale varies between exports.
+    satisfaction_range: (f64, f64),
+    // Accept numbers like "85,000". Off by default since a comma may also be
+    // a decimal separator.
+    thousands_separators: bool,
+    // Fall back to the default position for missing headers instead of failing.
+    lenient_columns: bool,
+    // Also require each expected header at its default position (--limit-columns).
+    fixed_column_positions: bool,
+}
+
+impl ReadOptions {
+    // Inclusive plausible bounds for each NUMERIC_COLUMNS value.
+    fn valid_ranges(&self) -> [(f64, f64); 6] {
+        [
+            (0.0, 120.0),
+            (0.0, f64::INFINITY),
+            self.satisfaction_range,
+            (0.0, f64::INFINITY),
+            (0.0, f64::INFINITY),
+            (0.0, 1.0),
+        ]
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            impute: false,
+            delimiter: b',',
+            max_records: 20_000,
+            verbose: false,
+            satisfaction_range: (1.0, 10.0),
+            thousands_separators: false,
+            lenient_columns: false,
+            fixed_column_positions: false,
+        }
+    }
+}
+
+// Everything that can stop a run, so callers can match on the kind of
+// failure and main can pick an exit code for it.
+#[derive(Debug)]
+enum AnalysisError {
+    IoError(io::Error),
+    CsvError(csv::Error),
+    // The input was readable but not in the expected shape (e.g. headers).
+    ParseError(String),
+    EmptyDataset,
+    InvalidArgument(String),
+}
+
+impl AnalysisError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            AnalysisError::InvalidArgument(_) => 2,
+            AnalysisError::IoError(_) => 3,
+            AnalysisError::CsvError(_) | AnalysisError::ParseError(_) => 4,
+            AnalysisError::EmptyDataset => 5,
+        }
+    }
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::IoError(err) => write!(f, "I/O error: {}", err),
+            AnalysisError::CsvError(err) => write!(f, "CSV error: {}", err),
+            AnalysisError::ParseError(message) => write!(f, "{}", message),
+            AnalysisError::EmptyDataset => write!(f, "No individuals loaded from the dataset!"),
+            AnalysisError::InvalidArgument(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for AnalysisError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AnalysisError::IoError(err) => Some(err),
+            AnalysisError::CsvError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AnalysisError {
+    fn from(err: io::Error) -> Self {
+        AnalysisError::IoError(err)
+    }
+}
+
+impl From<csv::Error> for AnalysisError {
+    fn from(err: csv::Error) -> Self {
+        AnalysisError::CsvError(err)
+    }
+}
+
+// A data record that read_dataset rejected.
+#[derive(Debug, Clone, PartialEq)]
+struct ParseError {
+    // 0-based index of the data record (the header row isn't counted).
+    record_index: usize,
+    reason: String,
+}
+
+// What happened while reading, beyond the rows that were kept.
+#[derive(Debug, Clone, Default)]
+struct ReadReport {
+    parse_errors: Vec<ParseError>,
+    // Rows left unread because of the max_records cap.
+    skipped: usize,
+    // Rows dropped because a value fell outside ReadOptions::valid_ranges.
+    out_of_range_rows: usize,
+    // Out-of-range values per NUMERIC_COLUMNS entry.
+    out_of_range: [usize; 6],
+    // Data records read (before the cap), whether kept or not.
+    records_read: usize,
+    // Parse errors that were short records, too few fields to parse at all.
+    short_records: usize,
+    // Missing or unparsable cells per NUMERIC_COLUMNS entry, and for the
+    // family influence column.
+    missing: [usize; 6],
+    missing_family_influence: usize,
+}
+
+// A dataset or --join path that means standard input instead of a file.
+const STDIN_PATH: &str = "-";
+
+// Opens the dataset file, or standard input for STDIN_PATH, transparently
+// decompressing `.gz` paths when the `gzip` feature is enabled.
+fn open_input(file_path: &str) -> Result<Box<dyn Read>, AnalysisError> {
+    if file_path == STDIN_PATH {
+        return Ok(Box::new(io::stdin()));
+    }
+    let file = File::open(file_path)?;
+    if file_path.ends_with(".gz") {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(flate2::read::GzDecoder::new(file)));
+        #[cfg(not(feature = "gzip"))]
+        return Err(AnalysisError::InvalidArgument(format!(
+            "'{}' is gzip-compressed; rebuild with `--features gzip` to read it",
+            file_path
+        )));
+    }
+    Ok(Box::new(file))
+}
+
+// How often a progress line is printed while reading, in records.
+const PROGRESS_INTERVAL: usize = 5_000;
+// Files at least this large (on disk) show progress even without --verbose.
+const PROGRESS_MIN_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+// Parses one numeric cell. With `thousands_separators`, stray quotes and
+// grouping commas are removed first. "NaN" and "inf" parse as f64 but would
+// slip past the range checks, so non-finite values count as unparsable.
+fn parse_numeric(raw: &str, thousands_separators: bool) -> Option<f64> {
+    let value = raw.trim();
+    let parsed: Option<f64> = if thousands_separators {
+        value.trim_matches('"').replace(',', "").trim().parse().ok()
+    } else {
+        value.parse().ok()
+    };
+    parsed.filter(|v| v.is_finite())
+}
+
+// Reads the dataset one record at a time, handing every kept row to `visit`
+// so callers decide whether to materialize them.
+fn for_each_raw_row(
+    file_path: &str,
+    options: &ReadOptions,
+    mut visit: impl FnMut(RawRow),
+) -> Result<ReadReport, AnalysisError> {
+    // Flexible so a short row is reported as a parse error below rather than
+    // aborting the whole read.
     let mut rdr = csv::ReaderBuilder::new()
         .has_headers(true) 
-        .from_path(file_path)?;
+        .flexible(true)
+        .delimiter(options.delimiter)
+        .from_reader(open_input(file_path)?);
+
+    let headers = rdr.headers()?;
+    if !options.lenient_columns {
+        ColumnConfig::validate_headers(headers, options.fixed_column_positions).map_err(AnalysisError::ParseError)?;
+    }
+    let columns = ColumnConfig::from_headers(headers);
+    let required_len = columns.required_len();
+    let numeric_indices = columns.numeric_indices();
+
+    let ranges = options.valid_ranges();
+    let mut report = ReadReport::default();
+
+    let file_bytes = std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+    let show_progress = options.verbose || file_bytes >= PROGRESS_MIN_FILE_BYTES;
+    let mut progress_shown = false;
+
+    for (i, result) in rdr.records().enumerate() {
+        let record = result?;
+
+        // Overwrites a single status line so large reads don't look hung.
+        if show_progress && (i + 1) % PROGRESS_INTERVAL == 0 {
+            eprint!(
+                "\rRead {} records ({} errors so far)",
+                i + 1,
+                report.parse_errors.len() + report.out_of_range_rows
+            );
+            progress_shown = true;
+        }
+
+        // Past the cap, keep reading only to report how much was left out.
+        if i >= options.max_records {
+            report.skipped += 1;
+            continue;
+        }
+
+        report.records_read += 1;
+
+        if record.len() < required_len {
+            report.short_records += 1;
+            if options.verbose {
+                eprintln!("Short record at index {}: {:?}", i, record);
+            }
+            report.parse_errors.push(ParseError {
+                record_index: i,
+                reason: format!(
+                    "short record ({} fields, expected at least {})",
+                    record.len(),
+                    required_len
+                ),
+            });
+            continue;
+        }
+
+        // Reported untrimmed so the exact rejected string shows up in the log.
+        let raw_influence = &record[columns.family_influence];
+        let family_influence = family_influence_value(raw_influence)
+            .ok_or_else(|| format!("invalid Family Influence value '{}'", raw_influence));
+
+        let values = numeric_indices.map(|idx| parse_numeric(&record[idx], options.thousands_separators));
+        for (count, value) in report.missing.iter_mut().zip(&values) {
+            if value.is_none() {
+                *count += 1;
+            }
+        }
+        if family_influence.is_err() {
+            report.missing_family_influence += 1;
+        }
+
+        // Without imputation a row must parse completely to be kept.
+        let reason = match family_influence {
+            Ok(family_influence) if options.impute || values.iter().all(Option::is_some) => {
+                // Implausible values are dropped too, but counted apart from
+                // parse errors. Cells awaiting imputation are not checked.
+                let mut in_range = true;
+                for (col, value) in values.iter().enumerate() {
+                    let (low, high) = ranges[col];
+                    if matches!(value, Some(v) if *v < low || *v > high) {
+                        report.out_of_range[col] += 1;
+                        in_range = false;
+                    }
+                }
+                if in_range {
+                    visit((i, family_influence, values));
+                } else {
+                    report.out_of_range_rows += 1;
+                    if options.verbose {
+                        eprintln!("Warning: Out-of-range value in record {}", i);
+                    }
+                }
+                continue;
+            }
+            Err(reason) => reason,
+            Ok(_) => {
+                let failed: Vec<&str> = NUMERIC_COLUMNS
+                    .iter()
+                    .zip(&values)
+                    .filter(|(_, value)| value.is_none())
+                    .map(|(name, _)| *name)
+                    .collect();
+                format!("could not parse {}", failed.join(", "))
+            }
+        };
+        if options.verbose {
+            eprintln!("Warning: Could not parse data for record {}: {}", i, reason);
+        }
+        report.parse_errors.push(ParseError {
+            record_index: i,
+            reason,
+        });
+    }
+    if progress_shown {
+        eprintln!();
+    }
+
+    eprintln!("Total parse errors: {}", report.parse_errors.len());
+    print_missing_summary(&report);
+    if report.out_of_range_rows > 0 {
+        eprintln!("Dropped {} rows with out-of-range values:", report.out_of_range_rows);
+        for (name, count) in NUMERIC_COLUMNS.iter().zip(report.out_of_range) {
+            if count > 0 {
+                eprintln!("{}: {}", name, count);
+            }
+        }
+    }
+    Ok(report)
+}
+
+// Per-column counts of missing or unparsable cells, as a share of the
+// records read.
+fn print_missing_summary(report: &ReadReport) {
+    if report.missing.iter().all(|&count| count == 0) && report.missing_family_influence == 0 {
+        eprintln!("Missing or unparsable values: none");
+        return;
+    }
+    eprintln!("Missing or unparsable values per column:");
+    let columns = NUMERIC_COLUMNS
+        .iter()
+        .copied()
+        .zip(report.missing)
+        .chain([(FAMILY_INFLUENCE_COLUMN, report.missing_family_influence)]);
+    for (name, count) in columns {
+        eprintln!(
+            "{}: {} missing ({:.1}%)",
+            name,
+            count,
+            count as f64 / report.records_read as f64 * 100.0
+        );
+    }
+}
+
+// Rows removed after loading, and kept by sampling, for pipeline_accounting.
+#[derive(Debug, Clone, Default)]
+struct PipelineCounts {
+    duplicates: usize,
+    unjoined: usize,
+    filtered: usize,
+    sampled: usize,
+    trimmed: usize,
+    // Non-positive salaries skipped by --log-target.
+    log_dropped: usize,
+}
+
+// (stage, rows) from the raw records through to the analysis sample. Each
+// "Rows ..." total equals the previous one minus the drops between them.
+fn pipeline_accounting(report: &ReadReport, counts: &PipelineCounts) -> Vec<(&'static str, usize)> {
+    let loaded = report.records_read - report.parse_errors.len() - report.out_of_range_rows;
+    let available = loaded - counts.duplicates - counts.unjoined - counts.filtered;
+    vec![
+        ("Left unread by the record cap", report.skipped),
+        ("Rows read", report.records_read),
+        ("Dropped as short records", report.short_records),
+        ("Dropped for parse errors", report.parse_errors.len() - report.short_records),
+        ("Dropped for out-of-range values", report.out_of_range_rows),
+        ("Rows loaded", loaded),
+        ("Dropped as duplicates", counts.duplicates),
+        ("Dropped without a join match", counts.unjoined),
+        ("Dropped by --filter", counts.filtered),
+        ("Rows available for sampling", available),
+        ("Rows after sampling", counts.sampled),
+        ("Dropped as salary outliers", counts.trimmed),
+        ("Dropped by log transform", counts.log_dropped),
+        ("Rows used in analysis", counts.sampled - counts.trimmed - counts.log_dropped),
+    ]
+}
+
+fn print_pipeline_accounting(report: &ReadReport, counts: &PipelineCounts) {
+    println!("\n--- Pipeline Accounting ---");
+    for (stage, rows) in pipeline_accounting(report, counts) {
+        println!("{:<32} {:>8}", stage, rows);
+    }
+}
+
+// Share of the records read that were rejected, by parse error or range
+// check. NaN when nothing was read.
+fn error_rate(report: &ReadReport) -> f64 {
+    (report.parse_errors.len() + report.out_of_range_rows) as f64 / report.records_read as f64
+}
+
+// The --check summary: passes when the error rate is at most `max_error_rate`.
+fn check_dataset(kept: usize, report: &ReadReport, max_error_rate: f64) -> Result<(), AnalysisError> {
+    if report.records_read == 0 {
+        return Err(AnalysisError::EmptyDataset);
+    }
+    let rate = error_rate(report);
+    println!(
+        "Rows read: {}, kept: {}, parse errors: {}, out of range: {} (error rate {:.2}%)",
+        report.records_read,
+        kept,
+        report.parse_errors.len(),
+        report.out_of_range_rows,
+        rate * 100.0
+    );
+    if rate > max_error_rate {
+        return Err(AnalysisError::ParseError(format!(
+            "error rate {:.2}% exceeds the --check threshold of {:.2}%",
+            rate * 100.0,
+            max_error_rate * 100.0
+        )));
+    }
+    println!("Check passed");
+    Ok(())
+}
+
+// Missing values (only left when imputation failed) become NaN.
+fn individual_from_raw((id, family_influence, values): RawRow) -> Individual {
+    let [
+        age,
+        years_of_experience,
+        job_satisfaction,
+        professional_network_size,
+        salary,
+        likelihood_to_change_occupation,
+    ] = values.map(|value| value.unwrap_or(f64::NAN));
+
+    Individual {
+        id,
+        age,
+        years_of_experience,
+        job_satisfaction,
+        professional_network_size,
+        family_influence,
+        salary,
+        likelihood_to_change_occupation,
+        external: f64::NAN,
+        imputed: false,
+    }
+}
+
+// Reads one or more files with the same options and concatenates them.
+// Record indices (and so ids) continue from one file to the next,
+// max_records caps the combined total, and imputation uses means over all
+// files.
+fn read_dataset(
+    file_paths: &[&str],
+    options: &ReadOptions,
+) -> Result<(Vec<Individual>, ReadReport), AnalysisError> {
+    let mut rows: Vec<RawRow> = Vec::new();
+    let mut report = ReadReport::default();
+    let mut offset = 0;
+
+    for file_path in file_paths {
+        let file_options = ReadOptions {
+            max_records: options.max_records.saturating_sub(offset),
+            ..options.clone()
+        };
+        let before = rows.len();
+        let file_report = for_each_raw_row(file_path, &file_options, |(i, influence, values)| {
+            rows.push((offset + i, influence, values))
+        })?;
+
+        let kept = rows.len() - before;
+        if file_paths.len() > 1 {
+            eprintln!(
+                "{}: {} rows kept, {} parse errors",
+                file_path,
+                kept,
+                file_report.parse_errors.len()
+            );
+        }
+
+        report.parse_errors.extend(file_report.parse_errors.iter().map(|error| ParseError {
+            record_index: offset + error.record_index,
+            reason: error.reason.clone(),
+        }));
+        report.skipped += file_report.skipped;
+        report.out_of_range_rows += file_report.out_of_range_rows;
+        for (total, count) in report.out_of_range.iter_mut().zip(file_report.out_of_range) {
+            *total += count;
+        }
+        for (total, count) in report.missing.iter_mut().zip(file_report.missing) {
+            *total += count;
+        }
+        report.missing_family_influence += file_report.missing_family_influence;
+        report.records_read += file_report.records_read;
+        report.short_records += file_report.short_records;
+        offset += kept
+            + file_report.parse_errors.len()
+            + file_report.out_of_range_rows
+            + file_report.skipped;
+    }
+
+    if report.skipped > 0 {
+        eprintln!(
+            "Record cap of {} reached; skipped {} remaining rows (raise it with --max-records)",
+            options.max_records, report.skipped
+        );
+    }
+
+    // Rows still holding a None here are the ones imputation will fill in.
+    let incomplete: Vec<bool> = rows
+        .iter()
+        .map(|(_, _, values)| values.iter().any(Option::is_none))
+        .collect();
+
+    if options.impute {
+        let imputed = impute_column_means(&mut rows);
+        eprintln!("Imputed values per column:");
+        for (name, count) in NUMERIC_COLUMNS.iter().zip(imputed) {
+            eprintln!("{}: {}", name, count);
+        }
+    }
+
+    let individuals = rows
+        .into_iter()
+        .zip(incomplete)
+        .map(|(row, incomplete)| Individual {
+            imputed: incomplete,
+            ..individual_from_raw(row)
+        })
+        .collect();
+    Ok((individuals, report))
+}
+
+// One step of Algorithm R: `item` is the `seen`-th item (1-based) of the
+// stream and replaces a random reservoir slot with probability k / seen.
+fn reservoir_offer(
+    reservoir: &mut Vec<Individual>,
+    k: usize,
+    seen: usize,
+    item: Individual,
+    rng: &mut impl Rng,
+) {
+    if reservoir.len() < k {
+        reservoir.push(item);
+    } else {
+        let slot = rng.gen_range(0..seen);
+        if slot < k {
+            reservoir[slot] = item;
+        }
+    }
+}
+
+// Sample size for keeping `fraction` of `n` individuals: rounded to the
+// nearest count, but at least one.
+fn fraction_sample_size(fraction: f64, n: usize) -> usize {
+    ((fraction * n as f64).round() as usize).clamp(1, n.max(1))
+}
+
+// The first k individuals in ascending `field` order, ties broken by id and
+// missing values last. This is not a random sample but a deterministic head
+// after sorting, so it doesn't depend on the seed or the rand version.
+fn sorted_head(individuals: &[Individual], field: Field, k: usize) -> Vec<Individual> {
+    let mut sorted = individuals.to_vec();
+    sorted.sort_by(|a, b| field.get(a).total_cmp(&field.get(b)).then(a.id.cmp(&b.id)));
+    sorted.truncate(k);
+    sorted
+}
+
+// Weighted random sample of k individuals without replacement, each picked
+// with probability proportional to `weight_fn` (Efraimidis-Spirakis A-Res:
+// keep the k largest keys u^(1/w), compared as ln(u) / w). Individuals with
+// a non-positive or non-finite weight are ineligible. Returns every eligible
+// individual if there are fewer than k.
+fn weighted_sample(
+    individuals: &[Individual],
+    k: usize,
+    weight_fn: impl Fn(&Individual) -> f64,
+    rng: &mut impl Rng,
+) -> Vec<Individual> {
+    let mut keyed = Vec::with_capacity(individuals.len());
+    let mut ineligible = 0;
+    for individual in individuals {
+        let weight = weight_fn(individual);
+        if weight.is_finite() && weight > 0.0 {
+            // 1 - gen() lies in (0, 1], so the logarithm is finite.
+            let u: f64 = 1.0 - rng.gen::<f64>();
+            keyed.push((u.ln() / weight, individual));
+        } else {
+            ineligible += 1;
+        }
+    }
+    if ineligible > 0 {
+        eprintln!(
+            "Warning: {} individuals with a non-positive or non-finite weight are ineligible for sampling",
+            ineligible
+        );
+    }
+
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().take(k).map(|(_, individual)| individual.clone()).collect()
+}
+
+// Random sample of k individuals that keeps each family influence level's
+// share of the population. Quotas are rounded down and the leftover slots go
+// to the strata with the largest remainders, so the sizes add up to k; each
+// stratum is then sampled uniformly. Returns everything if k covers the
+// population.
+fn stratified_sample(individuals: &[Individual], k: usize, rng: &mut impl Rng) -> Vec<Individual> {
+    if k >= individuals.len() {
+        return individuals.to_vec();
+    }
+
+    let mut strata: Vec<Vec<Individual>> = vec![Vec::new(); FAMILY_INFLUENCE_LEVELS.len()];
+    for individual in individuals {
+        strata[individual.family_influence as usize].push(individual.clone());
+    }
+
+    let exact: Vec<f64> = strata
+        .iter()
+        .map(|stratum| k as f64 * stratum.len() as f64 / individuals.len() as f64)
+        .collect();
+    let mut quotas: Vec<usize> = exact.iter().map(|q| q.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..strata.len()).collect();
+    by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    let leftover = k - quotas.iter().sum::<usize>();
+    for &stratum in by_remainder.iter().take(leftover) {
+        quotas[stratum] += 1;
+    }
+
+    let mut sample: Vec<Individual> = strata
+        .into_iter()
+        .zip(quotas)
+        .flat_map(|(stratum, quota)| reservoir_sample(stratum.into_iter(), quota, rng))
+        .collect();
+    sample.shuffle(rng);
+    sample
+}
+
+fn print_stratification(population: &[Individual], sample: &[Individual]) {
+    println!("\nFamily influence proportions (population -> stratified sample):");
+    let shares = family_influence_shares(population)
+        .into_iter()
+        .zip(family_influence_shares(sample));
+    for (label, (before, after)) in FAMILY_INFLUENCE_LEVELS.iter().zip(shares) {
+        println!("{}: {:.2}% -> {:.2}%", label, before * 100.0, after * 100.0);
+    }
+}
+
+// Uniform random sample of k individuals drawn in a single pass (Algorithm R),
+// holding at most k individuals at a time. Returns everything if the iterator
+// yields fewer than k.
+fn reservoir_sample(
+    individuals: impl Iterator<Item = Individual>,
+    k: usize,
+    rng: &mut impl Rng,
+) -> Vec<Individual> {
+    let mut reservoir = Vec::with_capacity(k);
+    for (i, individual) in individuals.enumerate() {
+        reservoir_offer(&mut reservoir, k, i + 1, individual, rng);
+    }
+    reservoir
+}
+
+// Streams every record into running regression sums for each analysis
+// so the full dataset is never held in memory; only a fixed-size uniform
+// sample (kept with reservoir sampling) is retained for the verification
+// section. Imputation needs column means up front, so it isn't available here.
+fn perform_streaming_analysis(
+    file_path: &str,
+    options: &ReadOptions,
+    sample_size: usize,
+    bins: usize,
+    analysis: &AnalysisOptions,
+    rng: &mut impl Rng,
+) -> Result<(), AnalysisError> {
+    let analyses = selected_analyses(analysis);
+    let target = analysis.target;
+    let mut sums = vec![RegressionSums::default(); analyses.len()];
+    let mut excluded = vec![0usize; analyses.len()];
+    let mut sample: Vec<Individual> = Vec::with_capacity(sample_size);
+    let mut seen = 0usize;
+
+    for_each_raw_row(file_path, options, |row| {
+        let individual = individual_from_raw(row);
+        for ((field, sums), excluded) in analyses.iter().zip(sums.iter_mut()).zip(excluded.iter_mut()) {
+            if !sums.add_finite(field.get(&individual), target.get(&individual)) {
+                *excluded += 1;
+            }
+        }
+
+        seen += 1;
+        reservoir_offer(&mut sample, sample_size, seen, individual, rng);
+    })?;
+
+    if seen == 0 {
+        return Err(AnalysisError::EmptyDataset);
+    }
+
+    print_sample_verification(&sample, bins, analysis.precision);
+
+    let digits = analysis.precision.unwrap_or(4);
+    println!("\n--- Streaming {} Correlation Analyses ({} records) ---", target.label(), seen);
+    for ((field, sums), &excluded) in analyses.iter().zip(&sums).zip(&excluded) {
+        if excluded > 0 {
+            eprintln!(
+                "Warning: excluded {} pairs with NaN or infinite values from {}",
+                excluded,
+                analysis.title(field.label())
+            );
+        }
+        let (slope, intercept, correlation, r_squared) = sums.regression();
+        println!("\n{}:", analysis.title(field.label()));
+        println!("Correlation Coefficient: {:.digits$}", correlation);
+        println!("Regression Equation: {} = {:.digits$} * X + {:.digits$}", target.label(), slope, intercept);
+        println!("R-squared: {:.digits$}", r_squared);
+        println!("{} correlation", correlation_strength(correlation, analysis.strength_thresholds));
+    }
+
+    Ok(())
+}
+
+// Replaces missing values with the mean of the parsed values in the same
+// column. Returns how many values were filled per column.
+fn impute_column_means(rows: &mut [RawRow]) -> [usize; 6] {
+    let mut sums = [0.0; 6];
+    let mut counts = [0usize; 6];
+    for (_, _, values) in rows.iter() {
+        for (col, value) in values.iter().enumerate() {
+            if let Some(v) = value {
+                sums[col] += v;
+                counts[col] += 1;
+            }
+        }
+    }
+
+    let mut imputed = [0usize; 6];
+    for (_, _, values) in rows.iter_mut() {
+        for (col, value) in values.iter_mut().enumerate() {
+            if value.is_none() {
+                *value = Some(sums[col] / counts[col] as f64);
+                imputed[col] += 1;
+            }
+        }
+    }
+
+    imputed
+}
+
+// Running sums behind simple regression and correlation, accumulated in a
+// single pass. Values are shifted by the first pair seen, which leaves every
+// derived statistic unchanged but avoids cancellation in sum_xx - sum_x^2/n
+// for large-magnitude data like salary.
+#[derive(Debug, Clone, Default)]
+struct RegressionSums {
+    n: f64,
+    shift_x: f64,
+    shift_y: f64,
+    sum_x: f64,
+    sum_y: f64,
+    sum_xx: f64,
+    sum_yy: f64,
+    sum_xy: f64,
+}
+
+impl RegressionSums {
+    fn from_slices(x: &[f64], y: &[f64]) -> Self {
+        assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+        let mut sums = RegressionSums::default();
+        for (&xi, &yi) in x.iter().zip(y) {
+            sums.add(xi, yi);
+        }
+        sums
+    }
+
+    fn add(&mut self, x: f64, y: f64) {
+        if self.n == 0.0 {
+            self.shift_x = x;
+            self.shift_y = y;
+        }
+        let dx = x - self.shift_x;
+        let dy = y - self.shift_y;
+        self.n += 1.0;
+        self.sum_x += dx;
+        self.sum_y += dy;
+        self.sum_xx += dx * dx;
+        self.sum_yy += dy * dy;
+        self.sum_xy += dx * dy;
+    }
+
+    // Adds the pair unless either value is NaN or infinite; returns whether
+    // it was added.
+    fn add_finite(&mut self, x: f64, y: f64) -> bool {
+        let finite = x.is_finite() && y.is_finite();
+        if finite {
+            self.add(x, y);
+        }
+        finite
+    }
+
+    // Centered sums of squares and cross-products: (S_xx, S_yy, S_xy).
+    fn centered(&self) -> (f64, f64, f64) {
+        (
+            self.sum_xx - self.sum_x * self.sum_x / self.n,
+            self.sum_yy - self.sum_y * self.sum_y / self.n,
+            self.sum_xy - self.sum_x * self.sum_y / self.n,
+        )
+    }
+
+    // Why the fit is (partly) undefined, if it is. Constant x leaves the
+    // slope undefined; constant y only leaves the correlation undefined.
+    fn degenerate_reason(&self) -> Option<&'static str> {
+        if self.n < 2.0 {
+            return Some("fewer than two data points");
+        }
+        let (s_xx, s_yy, _) = self.centered();
+        if s_xx <= 0.0 {
+            Some("x values have zero variance")
+        } else if s_yy <= 0.0 {
+            Some("y values have zero variance")
+        } else {
+            None
+        }
+    }
+
+    // Sample covariance S_xy / (n - 1); NaN for fewer than two points.
+    fn covariance(&self) -> f64 {
+        if self.n < 2.0 {
+            return f64::NAN;
+        }
+        let (_, _, s_xy) = self.centered();
+        s_xy / (self.n - 1.0)
+    }
+
+    fn correlation(&self) -> f64 {
+        if self.degenerate_reason().is_some() {
+            return f64::NAN;
+        }
+        let (s_xx, s_yy, s_xy) = self.centered();
+        s_xy / (s_xx * s_yy).sqrt()
+    }
+
+    // (slope, intercept, correlation, r_squared), as calculate_linear_regression.
+    fn regression(&self) -> (f64, f64, f64, f64) {
+        let (s_xx, _, _) = self.centered();
+        if self.n < 2.0 || s_xx <= 0.0 {
+            return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+        }
+        let slope = self.covariance() / (s_xx / (self.n - 1.0));
+        let mean_x = self.shift_x + self.sum_x / self.n;
+        let mean_y = self.shift_y + self.sum_y / self.n;
+        let intercept = mean_y - slope * mean_x;
+
+        let correlation = self.correlation();
+        let r_squared = correlation.powi(2);
+
+        (slope, intercept, correlation, r_squared)
+    }
+}
+
+// Returns (slope, intercept, correlation, r_squared). Pairs where either
+// value is NaN or infinite are left out with a warning. Degenerate inputs
+// (fewer than two points, or a constant variable) print a warning and report
+// the undefined statistics as NaN.
+fn calculate_linear_regression(x: &[f64], y: &[f64]) -> (f64, f64, f64, f64) {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let mut sums = RegressionSums::default();
+    let mut excluded = 0;
+    for (&xi, &yi) in x.iter().zip(y) {
+        if !sums.add_finite(xi, yi) {
+            excluded += 1;
+        }
+    }
+    if excluded > 0 {
+        eprintln!("Warning: excluded {} pairs with NaN or infinite values from the regression", excluded);
+    }
+    if let Some(reason) = sums.degenerate_reason() {
+        eprintln!("Warning: {}; undefined regression statistics are reported as NaN", reason);
+    }
+    sums.regression()
+}
+
+// Indices of the pairs where both x and y are finite.
+fn finite_pair_indices(x: &[f64], y: &[f64]) -> Vec<usize> {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    (0..x.len()).filter(|&i| x[i].is_finite() && y[i].is_finite()).collect()
+}
+
+// Weighted least squares fit of y = slope * x + intercept, where each point
+// contributes to the sums in proportion to its weight. Equal weights give the
+// same line as calculate_linear_regression. Returns (slope, intercept), NaN
+// with a warning when the weighted x values have no spread.
+fn weighted_linear_regression(x: &[f64], y: &[f64], w: &[f64]) -> (f64, f64) {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    assert_eq!(x.len(), w.len(), "Weights must match the number of points");
+    assert!(w.iter().all(|&wi| wi >= 0.0), "Weights must be non-negative");
+
+    let total: f64 = w.iter().sum();
+    let mean_x = x.iter().zip(w).map(|(xi, wi)| wi * xi).sum::<f64>() / total;
+    let mean_y = y.iter().zip(w).map(|(yi, wi)| wi * yi).sum::<f64>() / total;
+
+    let (mut s_xx, mut s_xy) = (0.0, 0.0);
+    for ((xi, yi), wi) in x.iter().zip(y).zip(w) {
+        s_xx += wi * (xi - mean_x).powi(2);
+        s_xy += wi * (xi - mean_x) * (yi - mean_y);
+    }
+
+    if s_xx.is_nan() || s_xx <= 0.0 {
+        eprintln!("Warning: weighted x values have zero variance; weighted regression is reported as NaN");
+        return (f64::NAN, f64::NAN);
+    }
+
+    let slope = s_xy / s_xx;
+    (slope, mean_y - slope * mean_x)
+}
+
+// IRLS stops once neither coefficient moves by more than this (relative).
+const HUBER_TOLERANCE: f64 = 1e-9;
+const HUBER_MAX_ITERATIONS: usize = 50;
+
+// Huber M-estimate of (slope, intercept) by iteratively reweighted least
+// squares, starting from the OLS fit: residuals within `delta` keep weight 1
+// and larger ones get delta / |r|. Returns the fit and the number of
+// iterations run, which equals `iters` when it did not converge. A
+// non-positive delta leaves the OLS fit unchanged.
+fn huber_regression(x: &[f64], y: &[f64], delta: f64, iters: usize) -> (f64, f64, usize) {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let (mut slope, mut intercept, _, _) = RegressionSums::from_slices(x, y).regression();
+    if delta.is_nan() || delta <= 0.0 || slope.is_nan() {
+        return (slope, intercept, 0);
+    }
+
+    for iteration in 1..=iters {
+        let weights: Vec<f64> = residuals(x, y, slope, intercept)
+            .iter()
+            .map(|r| if r.abs() <= delta { 1.0 } else { delta / r.abs() })
+            .collect();
+        let (new_slope, new_intercept) = weighted_linear_regression(x, y, &weights);
+        let converged = (new_slope - slope).abs() <= HUBER_TOLERANCE * (1.0 + slope.abs())
+            && (new_intercept - intercept).abs() <= HUBER_TOLERANCE * (1.0 + intercept.abs());
+        slope = new_slope;
+        intercept = new_intercept;
+        if converged {
+            return (slope, intercept, iteration);
+        }
+    }
+    (slope, intercept, iters)
+}
+
+// Median absolute deviation scaled by 1 / 0.6745, a robust estimate of the
+// standard deviation for normal data. NaN for empty input.
+fn robust_scale(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = percentile(&sorted, 0.5);
+    let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+    deviations.sort_by(|a, b| a.total_cmp(b));
+    percentile(&deviations, 0.5) / 0.6745
+}
+
+// Above this many point pairs theil_sen estimates the median slope from a
+// random sample of pairs instead of enumerating all of them.
+const THEIL_SEN_MAX_PAIRS: usize = 200_000;
+
+fn theil_sen_samples_pairs(n: usize) -> bool {
+    n * n.saturating_sub(1) / 2 > THEIL_SEN_MAX_PAIRS
+}
+
+// Theil-Sen robust line: the median slope over point pairs with distinct x,
+// and the median of y - slope * x as intercept. Large inputs use
+// THEIL_SEN_MAX_PAIRS random pairs from a fixed seed, so results stay
+// reproducible. Returns NaNs when every x is equal.
+fn theil_sen(x: &[f64], y: &[f64]) -> (f64, f64) {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let n = x.len();
+    let pair_slope = |i: usize, j: usize| (y[j] - y[i]) / (x[j] - x[i]);
+
+    let mut slopes = Vec::new();
+    if theil_sen_samples_pairs(n) {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..THEIL_SEN_MAX_PAIRS {
+            let (i, j) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            if x[i] != x[j] {
+                slopes.push(pair_slope(i, j));
+            }
+        }
+    } else {
+        for i in 0..n {
+            for j in (i + 1)..n {
+                if x[i] != x[j] {
+                    slopes.push(pair_slope(i, j));
+                }
+            }
+        }
+    }
+
+    if slopes.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    slopes.sort_by(|a, b| a.total_cmp(b));
+    let slope = percentile(&slopes, 0.5);
+
+    let mut offsets: Vec<f64> = x.iter().zip(y).map(|(xi, yi)| yi - slope * xi).collect();
+    offsets.sort_by(|a, b| a.total_cmp(b));
+    (slope, percentile(&offsets, 0.5))
+}
+
+// A fitted line, kept around so it can be reused for prediction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LinearModel {
+    slope: f64,
+    intercept: f64,
+}
+
+impl LinearModel {
+    // Fits by calculate_linear_regression; NaN coefficients on degenerate input.
+    fn fit(x: &[f64], y: &[f64]) -> Self {
+        let (slope, intercept, _, _) = calculate_linear_regression(x, y);
+        LinearModel { slope, intercept }
+    }
+
+    fn predict(&self, x: f64) -> f64 {
+        self.slope * x + self.intercept
+    }
+
+    fn predict_many(&self, xs: &[f64]) -> Vec<f64> {
+        xs.iter().map(|&x| self.predict(x)).collect()
+    }
+}
+
+// A fitted line with the training summaries a prediction interval needs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FittedLine {
+    model: LinearModel,
+    n: usize,
+    mean_x: f64,
+    // Sum of squared deviations of x from its mean.
+    s_xx: f64,
+    // See std_error_of_estimate.
+    std_error: f64,
+}
+
+impl FittedLine {
+    fn new(x: &[f64], model: LinearModel, std_error: f64) -> Self {
+        let n = x.len();
+        let mean_x = x.iter().sum::<f64>() / n as f64;
+        let s_xx = x.iter().map(|xi| (xi - mean_x).powi(2)).sum();
+        FittedLine { model, n, mean_x, s_xx, std_error }
+    }
+}
+
+// Bounds for a new observation at x0 at the given confidence level:
+// y_hat +/- t * s * sqrt(1 + 1/n + (x0 - mean_x)^2 / S_xx).
+fn prediction_interval(line: &FittedLine, x0: f64, level: f64) -> (f64, f64) {
+    let n = line.n as f64;
+    let leverage = (x0 - line.mean_x).powi(2) / line.s_xx;
+    let margin = t_critical_value(level, n - 2.0) * line.std_error * (1.0 + 1.0 / n + leverage).sqrt();
+    let predicted = line.model.predict(x0);
+    (predicted - margin, predicted + margin)
+}
+
+// Sample covariance of x and y (n - 1 denominator).
+fn covariance(x: &[f64], y: &[f64]) -> f64 {
+    RegressionSums::from_slices(x, y).covariance()
+}
+
+fn pearson_correlation(x: &[f64], y: &[f64]) -> f64 {
+    RegressionSums::from_slices(x, y).correlation()
+}
+
+// Pearson correlation for every pair of numeric fields, indexed in
+// Field::NUMERIC order.
+fn correlation_matrix(individuals: &[Individual]) -> Vec<Vec<f64>> {
+    let columns: Vec<Vec<f64>> = Field::NUMERIC
+        .iter()
+        .map(|field| individuals.iter().map(|ind| field.get(ind)).collect())
+        .collect();
+
+    let mut matrix = vec![vec![1.0; columns.len()]; columns.len()];
+    for i in 0..columns.len() {
+        for j in i + 1..columns.len() {
+            let r = pearson_correlation(&columns[i], &columns[j]);
+            matrix[i][j] = r;
+            matrix[j][i] = r;
+        }
+    }
+
+    matrix
+}
+
+fn print_correlation_matrix(individuals: &[Individual], precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    let labels: Vec<String> = Field::NUMERIC.iter().map(|field| field.short_label()).collect();
+    let matrix = correlation_matrix(individuals);
+
+    println!("\n--- Correlation Matrix ---");
+    print!("{:>13}", "");
+    for label in &labels {
+        print!("{:>13}", label);
+    }
+    println!();
+
+    for (label, row) in labels.iter().zip(&matrix) {
+        print!("{:>13}", label);
+        for value in row {
+            print!("{:>13.digits$}", value);
+        }
+        println!();
+    }
+}
+
+// Pearson correlation of `x` and `y` within windows of `window` individuals
+// sorted by `sort_by`, starting every `step` individuals. Returns (mean of
+// `sort_by` in the window, correlation) per window; the correlation is NaN
+// when the window has too few usable points or no variation.
+fn rolling_correlation(
+    individuals: &[Individual],
+    sort_by: Field,
+    x: Field,
+    y: Field,
+    window: usize,
+    step: usize,
+) -> Vec<(f64, f64)> {
+    assert!(window > 0 && step > 0, "Window and step must be positive");
+    let mut sorted: Vec<&Individual> = individuals.iter().collect();
+    sorted.sort_by(|a, b| sort_by.get(a).total_cmp(&sort_by.get(b)));
+
+    (0..sorted.len().saturating_sub(window - 1))
+        .step_by(step)
+        .map(|start| {
+            let members = &sorted[start..start + window];
+            let center = members.iter().map(|ind| sort_by.get(ind)).sum::<f64>() / window as f64;
+            let xs: Vec<f64> = members.iter().map(|ind| x.get(ind)).collect();
+            let ys: Vec<f64> = members.iter().map(|ind| y.get(ind)).collect();
+            (center, pearson_correlation(&xs, &ys))
+        })
+        .collect()
+}
+
+// How the salary-experience correlation moves across the age range.
+fn print_rolling_correlation(individuals: &[Individual], window: usize, step: usize, precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    let series = rolling_correlation(individuals, Field::Age, Field::Experience, Field::Salary, window, step);
+    println!(
+        "\n--- Rolling Salary vs Years of Experience Correlation by Age (window {}, step {}) ---",
+        window, step
+    );
+    if series.is_empty() {
+        println!("The sample has fewer than {} individuals; no windows to report", window);
+        return;
+    }
+    println!("{:>10} {:>12}", "Mean Age", "Correlation");
+    let mut skipped = 0;
+    for (center, correlation) in series {
+        if correlation.is_nan() {
+            skipped += 1;
+        } else {
+            println!("{:>10.2} {:>12.digits$}", center, correlation);
+        }
+    }
+    if skipped > 0 {
+        println!("Skipped {} windows with too few usable points for a correlation", skipped);
+    }
+}
+
+fn rmse(predicted: &[f64], actual: &[f64]) -> f64 {
+    assert_eq!(predicted.len(), actual.len(), "Input vectors must be of equal length");
+    let sum_sq: f64 = predicted.iter().zip(actual).map(|(p, a)| (p - a).powi(2)).sum();
+    (sum_sq / predicted.len() as f64).sqrt()
+}
+
+// 1 - SS_res / SS_tot for any predictions, not just an OLS line's (for
+// which it equals the squared correlation). NaN when `actual` is constant.
+fn r_squared_from_predictions(actual: &[f64], predicted: &[f64]) -> f64 {
+    assert_eq!(actual.len(), predicted.len(), "Input vectors must be of equal length");
+    let mean = actual.iter().sum::<f64>() / actual.len() as f64;
+    let ss_tot: f64 = actual.iter().map(|a| (a - mean).powi(2)).sum();
+    if ss_tot.is_nan() || ss_tot <= 0.0 {
+        return f64::NAN;
+    }
+    let ss_res: f64 = actual.iter().zip(predicted).map(|(a, p)| (a - p).powi(2)).sum();
+    1.0 - ss_res / ss_tot
+}
+
+fn mae(predicted: &[f64], actual: &[f64]) -> f64 {
+    assert_eq!(predicted.len(), actual.len(), "Input vectors must be of equal length");
+    let sum_abs: f64 = predicted.iter().zip(actual).map(|(p, a)| (p - a).abs()).sum();
+    sum_abs / predicted.len() as f64
+}
+
+// Converts values to 1-based ranks, giving tied values the average of the
+// ranks they span.
+fn rank_values(data: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| data[a].total_cmp(&data[b]));
+
+    let mut ranks = vec![0.0; data.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && data[order[end]] == data[order[start]] {
+            end += 1;
+        }
+        // Positions start..end hold ranks start+1..=end; their mean is:
+        let average_rank = (start + end + 1) as f64 / 2.0;
+        for &idx in &order[start..end] {
+            ranks[idx] = average_rank;
+        }
+        start = end;
+    }
+
+    ranks
+}
+
+fn spearman_correlation(x: &[f64], y: &[f64]) -> f64 {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    pearson_correlation(&rank_values(x), &rank_values(y))
+}
+
+// kendall_tau compares every pair of records, so it is skipped above this
+// many records to keep the analyses fast.
+const KENDALL_MAX_SAMPLE: usize = 5_000;
+
+// Kendall's tau-b: (concordant - discordant) pairs, normalized so that ties in
+// either variable are accounted for. NaN when x or y is constant. O(n^2).
+fn kendall_tau(x: &[f64], y: &[f64]) -> f64 {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let n = x.len();
+    let (mut concordant, mut discordant) = (0i64, 0i64);
+    let (mut ties_x, mut ties_y) = (0i64, 0i64);
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let dx = x[i] - x[j];
+            let dy = y[i] - y[j];
+            if dx == 0.0 {
+                ties_x += 1;
+            }
+            if dy == 0.0 {
+                ties_y += 1;
+            }
+            if dx * dy > 0.0 {
+                concordant += 1;
+            } else if dx * dy < 0.0 {
+                discordant += 1;
+            }
+        }
+    }
+
+    let pairs = (n * n.saturating_sub(1) / 2) as i64;
+    let denominator = (((pairs - ties_x) * (pairs - ties_y)) as f64).sqrt();
+    if denominator == 0.0 {
+        return f64::NAN;
+    }
+    (concordant - discordant) as f64 / denominator
+}
+
+// Shuffles a copy of the individuals and holds out `test_fraction` of them.
+// Returns (train, test).
+fn train_test_split(
+    individuals: &[Individual],
+    test_fraction: f64,
+    rng: &mut impl Rng,
+) -> (Vec<Individual>, Vec<Individual>) {
+    assert!(
+        test_fraction > 0.0 && test_fraction < 1.0,
+        "Test fraction must be between 0 and 1 (exclusive)"
+    );
+
+    let mut shuffled = individuals.to_vec();
+    shuffled.shuffle(rng);
+
+    let test_len = (shuffled.len() as f64 * test_fraction).round() as usize;
+    let test = shuffled.split_off(shuffled.len() - test_len);
+
+    (shuffled, test)
+}
+
+// Deterministic counterpart of train_test_split for --sort-by: the same
+// number of individuals is held out, but spaced evenly through the given
+// order (systematic sampling) instead of chosen at random.
+fn systematic_split(individuals: &[Individual], test_fraction: f64) -> (Vec<Individual>, Vec<Individual>) {
+    assert!(
+        test_fraction > 0.0 && test_fraction < 1.0,
+        "Test fraction must be between 0 and 1 (exclusive)"
+    );
+
+    let n = individuals.len();
+    let test_len = (n as f64 * test_fraction).round() as usize;
+    let mut train = Vec::with_capacity(n - test_len);
+    let mut test = Vec::with_capacity(test_len);
+    for (i, individual) in individuals.iter().enumerate() {
+        // Position i starts a new multiple of n / test_len exactly test_len times.
+        if (i + 1) * test_len / n > i * test_len / n {
+            test.push(individual.clone());
+        } else {
+            train.push(individual.clone());
+        }
+    }
+    (train, test)
+}
+
+// k-fold cross-validated R-squared of the salary regression on `predictor`.
+// The records are shuffled once, dealt round-robin into k folds, and each
+// fold is scored by a line fitted on the other k - 1. Returns the mean and
+// sample standard deviation of the k held-out R-squared values.
+fn k_fold_cv(
+    individuals: &[Individual],
+    k: usize,
+    predictor: Field,
+    target: Field,
+    rng: &mut impl Rng,
+) -> (f64, f64) {
+    assert!(k >= 2, "Cross-validation needs at least two folds");
+    assert!(individuals.len() >= k, "Cross-validation needs at least one record per fold");
+
+    let mut order: Vec<usize> = (0..individuals.len()).collect();
+    order.shuffle(rng);
+
+    let scores: Vec<f64> = (0..k)
+        .map(|fold| {
+            let (mut x, mut y) = (Vec::new(), Vec::new());
+            let (mut held_x, mut held_y) = (Vec::new(), Vec::new());
+            for (position, &i) in order.iter().enumerate() {
+                let (xs, ys) = if position % k == fold {
+                    (&mut held_x, &mut held_y)
+                } else {
+                    (&mut x, &mut y)
+                };
+                xs.push(predictor.get(&individuals[i]));
+                ys.push(target.get(&individuals[i]));
+            }
+
+            let model = LinearModel::fit(&x, &y);
+            r_squared_from_predictions(&held_y, &model.predict_many(&held_x))
+        })
+        .collect();
+
+    let stats = compute_stats(&scores);
+    (stats.mean, stats.std_dev)
+}
+
+// Percentile bootstrap 95% interval for the Pearson correlation: resamples
+// (x, y) pairs with replacement and returns the 2.5th and 97.5th percentiles
+// of the resampled correlations. Degenerate resamples (constant x or y) are
+// left out; NaNs when there are none left.
+fn bootstrap_correlation_ci(
+    x: &[f64],
+    y: &[f64],
+    n_resamples: usize,
+    rng: &mut impl Rng,
+) -> (f64, f64) {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let n = x.len();
+    if n == 0 {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let mut correlations = Vec::with_capacity(n_resamples);
+    for _ in 0..n_resamples {
+        let mut sums = RegressionSums::default();
+        for _ in 0..n {
+            let i = rng.gen_range(0..n);
+            sums.add(x[i], y[i]);
+        }
+        let correlation = sums.correlation();
+        if !correlation.is_nan() {
+            correlations.push(correlation);
+        }
+    }
+
+    if correlations.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    correlations.sort_by(|a, b| a.total_cmp(b));
+    (percentile(&correlations, 0.025), percentile(&correlations, 0.975))
+}
+
+// Settings for the per-analysis statistics.
+#[derive(Debug, Clone)]
+struct AnalysisOptions {
+    // Confidence level for the slope interval, in (0, 1).
+    confidence_level: f64,
+    // The predictors to analyze; empty runs all of them.
+    features: Vec<Field>,
+    // The response variable; it is left out of the predictors.
+    target: Field,
+    // Salary has already been replaced by ln(salary); only affects labels.
+    log_target: bool,
+    // Resamples for the bootstrap correlation interval; 0 skips it.
+    bootstrap_resamples: usize,
+    // Seeds each analysis's bootstrap so results don't depend on scheduling.
+    bootstrap_seed: u64,
+    // Huber threshold as a multiple of the robust scale of the OLS residuals.
+    huber_k: f64,
+    // Use Spearman as the headline correlation when an ordinal field is
+    // involved.
+    auto_rank_ordinal: bool,
+    // (weak, strong) cutoffs on |r| for correlation_strength.
+    strength_thresholds: (f64, f64),
+    // A --join column is loaded, so the external field can be analyzed.
+    joined: bool,
+    // Decimals for printed statistics; None keeps each section's default.
+    precision: Option<usize>,
+    // Predictor value for --predict-at; each analysis reports its
+    // prediction and prediction interval there at confidence_level.
+    predict_at: Option<f64>,
+}
+
+impl AnalysisOptions {
+    // The regression response as shown in titles and equations.
+    fn response(&self) -> &'static str {
+        if self.log_target {
+            "log(Salary)"
+        } else {
+            self.target.label()
+        }
+    }
+
+    // An analysis title such as "Salary vs Age".
+    fn title(&self, label: &str) -> String {
+        format!("{} vs {}", self.response(), label)
+    }
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            confidence_level: 0.95,
+            features: Vec::new(),
+            target: Field::Salary,
+            log_target: false,
+            bootstrap_resamples: 1_000,
+            bootstrap_seed: 0,
+            huber_k: 1.345,
+            auto_rank_ordinal: false,
+            strength_thresholds: (0.3, 0.7),
+            joined: false,
+            predict_at: None,
+            precision: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CorrelationResult {
+    title: String,
+    // The response label, e.g. "Salary" or "log(Salary)".
+    response: &'static str,
+    // The response is ln(salary), so fit statistics are on the log scale.
+    log_target: bool,
+    slope: f64,
+    intercept: f64,
+    slope_std_error: f64,
+    // Residual standard error, sqrt(SS_res / (n - 2)), on the response's scale.
+    std_error_of_estimate: f64,
+    // (x0, predicted y, prediction interval) for --predict-at.
+    prediction: Option<(f64, f64, (f64, f64))>,
+    // Confidence interval for the slope at `confidence_level`.
+    confidence_level: f64,
+    slope_ci: (f64, f64),
+    // (slope, intercept) with imputed records weighted by IMPUTED_ROW_WEIGHT;
+    // None when no training record was imputed.
+    weighted_fit: Option<(f64, f64)>,
+    covariance: f64,
+    correlation: f64,
+    // Spearman rather than Pearson is the headline correlation.
+    rank_based: bool,
+    // correlation_strength of the primary correlation.
+    strength: &'static str,
+    // 95% percentile-bootstrap interval for `correlation`; None when disabled.
+    bootstrap_ci: Option<(f64, f64)>,
+    spearman: f64,
+    // Robust (slope, intercept) from theil_sen, and whether it sampled
+    // THEIL_SEN_MAX_PAIRS pairs rather than using them all.
+    theil_sen: (f64, f64),
+    theil_sen_sampled: bool,
+    // Robust (slope, intercept) from huber_regression and its IRLS iterations.
+    huber: (f64, f64),
+    huber_iterations: usize,
+    // None when the sample exceeds KENDALL_MAX_SAMPLE.
+    kendall: Option<f64>,
+    r_squared: f64,
+    adjusted_r_squared: f64,
+    // Significance of the Pearson correlation (t-test with n - 2 df).
+    t_statistic: f64,
+    p_value: f64,
+    rmse: f64,
+    mae: f64,
+    // Summary of the training residuals, for checking model assumptions.
+    residual_stats: DescriptiveStats,
+    // Out-of-sample metrics; None when the test set is empty.
+    test_r_squared: Option<f64>,
+    test_rmse: Option<f64>,
+}
+
+impl CorrelationResult {
+    fn response(&self) -> &'static str {
+        self.response
+    }
+
+    // The correlation used for the strength label and ranking.
+    fn primary_correlation(&self) -> f64 {
+        if self.rank_based {
+            self.spearman
+        } else {
+            self.correlation
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        if self.rank_based {
+            "Spearman"
+        } else {
+            "Pearson"
+        }
+    }
+
+    fn model(&self) -> LinearModel {
+        LinearModel {
+            slope: self.slope,
+            intercept: self.intercept,
+        }
+    }
+}
+
+// Natural log of the gamma function (Lanczos approximation, g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula for the left half-plane.
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+
+        let even = m * (b - m) * x / ((a + m2 - 1.0) * (a + m2));
+        d = 1.0 + even * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1.0 + even / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1.0 / d;
+        result *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0));
+        d = 1.0 + odd * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1.0 + odd / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1.0 / d;
+        let delta = d * c;
+        result *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    result
+}
+
+// Regularized incomplete beta function I_x(a, b).
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    let front = ln_front.exp();
+
+    // The continued fraction converges fastest below the mean of the distribution.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Two-tailed p-value of a Student's t statistic with `df` degrees of freedom.
+fn t_test_p_value(t: f64, df: f64) -> f64 {
+    if t.is_nan() || df <= 0.0 {
+        return f64::NAN;
+    }
+    if t.is_infinite() {
+        return 0.0;
+    }
+    regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+// Upper-tail p-value of an F statistic with (df1, df2) degrees of freedom.
+fn f_test_p_value(f: f64, df1: f64, df2: f64) -> f64 {
+    if f.is_nan() || df1 <= 0.0 || df2 <= 0.0 {
+        return f64::NAN;
+    }
+    regularized_incomplete_beta(df2 / 2.0, df1 / 2.0, df2 / (df2 + df1 * f))
+}
+
+// One-way ANOVA across groups: (F, p-value) from the between-group and
+// within-group sums of squares. NaN with fewer than two groups or no
+// within-group degrees of freedom.
+fn one_way_anova(groups: &[Vec<f64>]) -> (f64, f64) {
+    let n: usize = groups.iter().map(Vec::len).sum();
+    let k = groups.len();
+    if k < 2 || n <= k {
+        return (f64::NAN, f64::NAN);
+    }
+    let grand_mean = groups.iter().flatten().sum::<f64>() / n as f64;
+
+    let mut ss_between = 0.0;
+    let mut ss_within = 0.0;
+    for group in groups {
+        let mean = group.iter().sum::<f64>() / group.len() as f64;
+        ss_between += group.len() as f64 * (mean - grand_mean).powi(2);
+        ss_within += group.iter().map(|v| (v - mean).powi(2)).sum::<f64>();
+    }
+
+    let df_between = (k - 1) as f64;
+    let df_within = (n - k) as f64;
+    let f = (ss_between / df_between) / (ss_within / df_within);
+    (f, f_test_p_value(f, df_between, df_within))
+}
+
+// t = r * sqrt((n - 2) / (1 - r^2)) for testing a correlation against zero.
+// A perfect correlation gives an infinite t; fewer than three points gives NaN.
+fn correlation_t_statistic(r: f64, n: usize) -> f64 {
+    if n < 3 || r.is_nan() {
+        return f64::NAN;
+    }
+    if r.abs() >= 1.0 {
+        return f64::INFINITY.copysign(r);
+    }
+    r * ((n as f64 - 2.0) / (1.0 - r * r)).sqrt()
+}
+
+// Critical value t* with P(|T| > t*) = 1 - level for `df` degrees of
+// freedom, found by bisection on the two-tailed p-value.
+fn t_critical_value(level: f64, df: f64) -> f64 {
+    let alpha = 1.0 - level;
+    let (mut low, mut high) = (0.0, 1.0);
+    while t_test_p_value(high, df) > alpha {
+        high *= 2.0;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if t_test_p_value(mid, df) > alpha {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Residual standard error (the standard error of the estimate),
+// sqrt(SS_res / (n - 2)); NaN with a warning when n <= 2 leaves no degrees
+// of freedom.
+fn std_error_of_estimate(x: &[f64], y: &[f64], slope: f64, intercept: f64) -> f64 {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    if x.len() <= 2 {
+        eprintln!("Warning: standard error of the estimate needs more than 2 points, got {}", x.len());
+        return f64::NAN;
+    }
+    let ss_res: f64 = residuals(x, y, slope, intercept).iter().map(|r| r * r).sum();
+    (ss_res / (x.len() as f64 - 2.0)).sqrt()
+}
+
+// Standard error of the OLS slope: sqrt(SS_res / (n - 2)) / sqrt(S_xx).
+fn slope_standard_error(x: &[f64], y: &[f64], slope: f64, intercept: f64) -> f64 {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    if x.len() < 3 {
+        return f64::NAN;
+    }
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let s_xx: f64 = x.iter().map(|xi| (xi - mean_x).powi(2)).sum();
+
+    std_error_of_estimate(x, y, slope, intercept) / s_xx.sqrt()
+}
+
+// Observed minus fitted values, y_i - (slope * x_i + intercept). For an OLS
+// fit these average to zero up to rounding.
+fn residuals(x: &[f64], y: &[f64], slope: f64, intercept: f64) -> Vec<f64> {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let model = LinearModel { slope, intercept };
+    x.iter().zip(y).map(|(xi, yi)| yi - model.predict(*xi)).collect()
+}
+
+// `(weak, strong)` cutoffs: |r| below weak is Weak, below strong Moderate.
+fn correlation_strength(correlation: f64, (weak, strong): (f64, f64)) -> &'static str {
+    if correlation.is_nan() {
+        "Undefined"
+    } else if correlation.abs() < weak {
+        "Weak"
+    } else if correlation.abs() < strong {
+        "Moderate"
+    } else {
+        "Strong"
+    }
+}
+
+// A numeric field that can be a predictor or the --target, requested by
+// its short name on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Salary,
+    Age,
+    Experience,
+    Satisfaction,
+    Network,
+    Influence,
+    Likelihood,
+    // The column attached with --join; only analyzed when one is loaded.
+    External,
+}
+
+impl Field {
+    // Every field, in the order the analyses run.
+    const ALL: [Field; 8] = [
+        Field::Salary,
+        Field::Age,
+        Field::Experience,
+        Field::Satisfaction,
+        Field::Network,
+        Field::Influence,
+        Field::Likelihood,
+        Field::External,
+    ];
+
+    // The seven fields every Individual carries, in display order.
+    const NUMERIC: [Field; 7] = [
+        Field::Age,
+        Field::Experience,
+        Field::Satisfaction,
+        Field::Network,
+        Field::Influence,
+        Field::Salary,
+        Field::Likelihood,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Salary => "salary",
+            Field::Age => "age",
+            Field::Experience => "experience",
+            Field::Satisfaction => "satisfaction",
+            Field::Network => "network",
+            Field::Influence => "influence",
+            Field::Likelihood => "likelihood",
+            Field::External => "external",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::Salary => "Salary",
+            Field::Age => "Age",
+            Field::Experience => "Years of Experience",
+            Field::Satisfaction => "Job Satisfaction",
+            Field::Network => "Professional Network Size",
+            Field::Influence => "Family Influence",
+            Field::Likelihood => "Likelihood to Change Occupation",
+            Field::External => "Joined Column",
+        }
+    }
+
+    // The capitalized short name, for narrow table headings.
+    fn short_label(self) -> String {
+        let name = self.name();
+        name[..1].to_uppercase() + &name[1..]
+    }
+
+    fn get(self, ind: &Individual) -> f64 {
+        match self {
+            Field::Salary => ind.salary,
+            Field::Age => ind.age,
+            Field::Experience => ind.years_of_experience,
+            Field::Satisfaction => ind.job_satisfaction,
+            Field::Network => ind.professional_network_size,
+            Field::Influence => ind.family_influence,
+            Field::Likelihood => ind.likelihood_to_change_occupation,
+            Field::External => ind.external,
+        }
+    }
+
+    fn get_mut(self, ind: &mut Individual) -> &mut f64 {
+        match self {
+            Field::Salary => &mut ind.salary,
+            Field::Age => &mut ind.age,
+            Field::Experience => &mut ind.years_of_experience,
+            Field::Satisfaction => &mut ind.job_satisfaction,
+            Field::Network => &mut ind.professional_network_size,
+            Field::Influence => &mut ind.family_influence,
+            Field::Likelihood => &mut ind.likelihood_to_change_occupation,
+            Field::External => &mut ind.external,
+        }
+    }
+
+    // Ordered categories rather than measurements; see
+    // AnalysisOptions::auto_rank_ordinal.
+    fn is_ordinal(self) -> bool {
+        matches!(self, Field::Influence | Field::Likelihood)
+    }
+
+    // Accepts the short name or the snake_case label, so "influence" and
+    // "family_influence" are the same field.
+    fn from_name(name: &str) -> Option<Field> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name() == name || field.label().to_lowercase().replace(' ', "_") == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    // Two-character operators come first so "<=" is not read as "<".
+    const ALL: [(&'static str, Comparison); 6] = [
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    fn symbol(self) -> &'static str {
+        Comparison::ALL
+            .iter()
+            .find(|(_, op)| *op == self)
+            .map(|(symbol, _)| *symbol)
+            .expect("every comparison has a symbol")
+    }
+
+    fn holds(self, x: f64, value: f64) -> bool {
+        match self {
+            Comparison::Lt => x < value,
+            Comparison::Le => x <= value,
+            Comparison::Gt => x > value,
+            Comparison::Ge => x >= value,
+            Comparison::Eq => x == value,
+            Comparison::Ne => x != value,
+        }
+    }
+}
+
+// A `field op value` condition from --filter, such as "age<40".
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Filter {
+    field: Field,
+    op: Comparison,
+    value: f64,
+}
+
+impl Filter {
+    fn parse(expr: &str) -> Result<Filter, String> {
+        let invalid = || format!("Invalid filter '{}': expected FIELD OP VALUE with OP one of <, <=, >, >=, ==, !=", expr);
+        let start = expr.find(['<', '>', '=', '!']).ok_or_else(invalid)?;
+        let (name, rest) = expr.split_at(start);
+        let (op, value) = Comparison::ALL
+            .iter()
+            .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|value| (*op, value)))
+            .ok_or_else(invalid)?;
+        let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+        Ok(Filter {
+            field: parse_field(name, "filter field")?,
+            op,
+            value,
+        })
+    }
+
+    // Missing (NaN) values never match, whatever the operator.
+    fn matches(&self, ind: &Individual) -> bool {
+        let x = self.field.get(ind);
+        !x.is_nan() && self.op.holds(x, self.value)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.field.name(), self.op.symbol(), self.value)
+    }
+}
+
+// Keeps the individuals matching every filter.
+fn apply_filters(individuals: Vec<Individual>, filters: &[Filter]) -> Vec<Individual> {
+    individuals
+        .into_iter()
+        .filter(|ind| filters.iter().all(|filter| filter.matches(ind)))
+        .collect()
+}
+
+// The individuals under analysis, with whole columns available by Field.
+#[derive(Debug, Clone, Default)]
+struct Dataset(Vec<Individual>);
+
+impl Dataset {
+    fn column(&self, field: Field) -> Vec<f64> {
+        self.0.iter().map(|ind| field.get(ind)).collect()
+    }
+
+    // The `x` and `y` columns without the rows where either is NaN or
+    // infinite, as compute_correlations drops them, warning with the count.
+    fn finite_pair_columns(&self, x: Field, y: Field) -> (Vec<f64>, Vec<f64>) {
+        let (xs, ys) = (self.column(x), self.column(y));
+        let kept = finite_pair_indices(&xs, &ys);
+        if kept.len() < xs.len() {
+            eprintln!(
+                "Warning: excluded {} pairs with NaN or infinite values from {} vs {}",
+                xs.len() - kept.len(),
+                y.label(),
+                x.label()
+            );
+        }
+        (kept.iter().map(|&i| xs[i]).collect(), kept.iter().map(|&i| ys[i]).collect())
+    }
+}
+
+impl std::ops::Deref for Dataset {
+    type Target = [Individual];
+
+    fn deref(&self) -> &[Individual] {
+        &self.0
+    }
+}
+
+// The predictors requested with --features, in their usual order, never
+// including the target itself.
+fn selected_analyses(options: &AnalysisOptions) -> Vec<Field> {
+    Field::ALL
+        .into_iter()
+        .filter(|&field| field != options.target)
+        .filter(|&field| options.joined || field != Field::External)
+        .filter(|field| options.features.is_empty() || options.features.contains(field))
+        .collect()
+}
+
+// Parses a comma-separated --features list, rejecting unknown names.
+fn parse_features(value: &str) -> Result<Vec<Field>, String> {
+    value.split(',').map(|name| parse_field(name, "feature")).collect()
+}
+
+// Parses a single field name; `role` names the flag in the error.
+fn parse_field(value: &str, role: &str) -> Result<Field, String> {
+    let name = value.trim();
+    Field::from_name(name).ok_or_else(|| {
+        let valid: Vec<&str> = Field::ALL.iter().map(|field| field.name()).collect();
+        format!("Unknown {} '{}': expected one of {}", role, name, valid.join(", "))
+    })
+}
+
+// Records with imputed values count this much in the weighted fit reported
+// alongside each regression.
+const IMPUTED_ROW_WEIGHT: f64 = 0.5;
+
+// Fits each regression of the target on `train` and evaluates it on the
+// held-out `test` set. Pure computation; see perform_correlation_analysis.
+fn compute_correlations(
+    train: &Dataset,
+    test: &Dataset,
+    options: &AnalysisOptions,
+) -> Vec<CorrelationResult> {
+    let analyses = selected_analyses(options);
+
+    let target = train.column(options.target);
+    let test_target = test.column(options.target);
+    let all_weights: Vec<f64> = train
+        .iter()
+        .map(|ind| if ind.imputed { IMPUTED_ROW_WEIGHT } else { 1.0 })
+        .collect();
+    let any_imputed = train.iter().any(|ind| ind.imputed);
+
+    // Each analysis is independent and pure, so with the `parallel` feature
+    // they run on rayon's pool; collect keeps the results in analysis order.
+    let analyze = |(index, &field): (usize, &Field)| {
+        // Drop pairs with a NaN or infinite value once, so every statistic
+        // and degree of freedom below sees the same points.
+        let column = train.column(field);
+        let kept = finite_pair_indices(&column, &target);
+        if kept.len() < column.len() {
+            eprintln!(
+                "Warning: excluded {} pairs with NaN or infinite values from {}",
+                column.len() - kept.len(),
+                options.title(field.label())
+            );
+        }
+        let pick = |values: &[f64]| -> Vec<f64> { kept.iter().map(|&i| values[i]).collect() };
+        let (x, y, weights) = (pick(&column), pick(&target), pick(&all_weights));
+
+        let (slope, intercept, correlation, r_squared) = 
+            calculate_linear_regression(&x, &y);
+
+        let model = LinearModel { slope, intercept };
+        let predicted = model.predict_many(&x);
+        let t_statistic = correlation_t_statistic(correlation, y.len());
+        let slope_std_error = slope_standard_error(&x, &y, slope, intercept);
+        let std_error_of_estimate = std_error_of_estimate(&x, &y, slope, intercept);
+        let margin = t_critical_value(options.confidence_level, y.len() as f64 - 2.0) * slope_std_error;
+
+        let (test_r_squared, test_rmse) = if test.is_empty() {
+            (None, None)
+        } else {
+            let test_column = test.column(field);
+            let test_kept = finite_pair_indices(&test_column, &test_target);
+            let test_x: Vec<f64> = test_kept.iter().map(|&i| test_column[i]).collect();
+            let test_y: Vec<f64> = test_kept.iter().map(|&i| test_target[i]).collect();
+            let test_predicted = model.predict_many(&test_x);
+            (
+                Some(r_squared_from_predictions(&test_y, &test_predicted)),
+                Some(rmse(&test_predicted, &test_y)),
+            )
+        };
+
+        let fit_residuals = residuals(&x, &y, slope, intercept);
+        let delta = options.huber_k * robust_scale(&fit_residuals);
+        let (huber_slope, huber_intercept, huber_iterations) =
+            huber_regression(&x, &y, delta, HUBER_MAX_ITERATIONS);
+
+        let spearman = spearman_correlation(&x, &y);
+        let rank_based = options.auto_rank_ordinal && (field.is_ordinal() || options.target.is_ordinal());
+
+        CorrelationResult {
+            title: options.title(field.label()),
+            response: options.response(),
+            log_target: options.log_target,
+            slope,
+            intercept,
+            slope_std_error,
+            std_error_of_estimate,
+            prediction: options.predict_at.map(|x0| {
+                let line = FittedLine::new(&x, model, std_error_of_estimate);
+                (x0, model.predict(x0), prediction_interval(&line, x0, options.confidence_level))
+            }),
+            confidence_level: options.confidence_level,
+            slope_ci: (slope - margin, slope + margin),
+            weighted_fit: any_imputed.then(|| weighted_linear_regression(&x, &y, &weights)),
+            covariance: covariance(&x, &y),
+            correlation,
+            rank_based,
+            strength: correlation_strength(
+                if rank_based { spearman } else { correlation },
+                options.strength_thresholds,
+            ),
+            bootstrap_ci: if options.bootstrap_resamples > 0 {
+                let mut rng = StdRng::seed_from_u64(options.bootstrap_seed.wrapping_add(index as u64));
+                Some(bootstrap_correlation_ci(&x, &y, options.bootstrap_resamples, &mut rng))
+            } else {
+                None
+            },
+            spearman,
+            theil_sen: theil_sen(&x, &y),
+            theil_sen_sampled: theil_sen_samples_pairs(x.len()),
+            huber: (huber_slope, huber_intercept),
+            huber_iterations,
+            kendall: (x.len() <= KENDALL_MAX_SAMPLE).then(|| kendall_tau(&x, &y)),
+            r_squared,
+            adjusted_r_squared: adjusted_r_squared(r_squared, y.len(), 1),
+            t_statistic,
+            p_value: t_test_p_value(t_statistic, y.len() as f64 - 2.0),
+            rmse: rmse(&predicted, &y),
+            mae: mae(&predicted, &y),
+            residual_stats: compute_stats(&fit_residuals),
+            test_r_squared,
+            test_rmse,
+        }
+    };
+
+    #[cfg(feature = "parallel")]
+    let results = analyses.par_iter().enumerate().map(analyze).collect();
+    #[cfg(not(feature = "parallel"))]
+    let results = analyses.iter().enumerate().map(analyze).collect();
+
+    results
+}
+
+fn print_correlation_result(result: &CorrelationResult, precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    // RMSE, MAE and residual spreads are on the response's scale.
+    let error_digits = precision.unwrap_or(2);
+    println!("\n{}:", result.title);
+    println!(
+        "Correlation Coefficient: Pearson = {:.digits$}, Spearman = {:.digits$}",
+        result.correlation, result.spearman
+    );
+    if result.rank_based {
+        println!("Method: Spearman (ordinal field)");
+    }
+    println!("Covariance: {:.digits$}", result.covariance);
+    if let Some((low, high)) = result.bootstrap_ci {
+        println!("Pearson 95% bootstrap CI: [{:.digits$}, {:.digits$}]", low, high);
+    }
+    match result.kendall {
+        Some(kendall) => println!("Kendall's tau-b: {:.digits$}", kendall),
+        None => println!(
+            "Kendall's tau-b: skipped (more than {} records)",
+            KENDALL_MAX_SAMPLE
+        ),
+    }
+    println!(
+        "Regression Equation: {} = {:.digits$} * X + {:.digits$}",
+        result.response(),
+        result.slope,
+        result.intercept
+    );
+    println!(
+        "Theil-Sen robust fit: {} = {:.digits$} * X + {:.digits$} (OLS slope {:.digits$}){}",
+        result.response(),
+        result.theil_sen.0,
+        result.theil_sen.1,
+        result.slope,
+        if result.theil_sen_sampled {
+            format!(", from {} sampled pairs", THEIL_SEN_MAX_PAIRS)
+        } else {
+            String::new()
+        }
+    );
+    println!(
+        "Huber robust fit: {} = {:.digits$} * X + {:.digits$} ({} IRLS iterations{})",
+        result.response(),
+        result.huber.0,
+        result.huber.1,
+        result.huber_iterations,
+        if result.huber_iterations == HUBER_MAX_ITERATIONS { ", not converged" } else { "" }
+    );
+    println!(
+        "slope = {:.digits$} ({}% CI: [{:.digits$}, {:.digits$}]), SE = {:.digits$}",
+        result.slope,
+        result.confidence_level * 100.0,
+        result.slope_ci.0,
+        result.slope_ci.1,
+        result.slope_std_error
+    );
+    println!("Standard error of the estimate: {:.digits$}", result.std_error_of_estimate);
+    if let Some((x0, predicted, (low, high))) = result.prediction {
+        println!(
+            "Prediction at X = {}: {:.digits$} ({}% PI: [{:.digits$}, {:.digits$}])",
+            x0,
+            predicted,
+            result.confidence_level * 100.0,
+            low,
+            high
+        );
+    }
+    if let Some((weighted_slope, weighted_intercept)) = result.weighted_fit {
+        println!(
+            "Weighted fit (imputed records x{}): {} = {:.digits$} * X + {:.digits$}",
+            IMPUTED_ROW_WEIGHT,
+            result.response(),
+            weighted_slope,
+            weighted_intercept
+        );
+    }
+    println!(
+        "R-squared: {:.digits$}, Adjusted R-squared: {:.digits$}{}",
+        result.r_squared,
+        result.adjusted_r_squared,
+        if result.log_target { " (log scale)" } else { "" }
+    );
+    println!(
+        "t-statistic: {:.digits$}, p-value: {:.digits$} ({})",
+        result.t_statistic,
+        result.p_value,
+        if result.p_value < 0.05 {
+            "significant at p < 0.05"
+        } else {
+            "not significant at p < 0.05"
+        }
+    );
+    println!("RMSE: {:.error_digits$}", result.rmse);
+    println!("MAE: {:.error_digits$}", result.mae);
+    println!(
+        "Residuals: mean = {:.digits$}, std dev = {:.error_digits$}, min = {:.error_digits$}, max = {:.error_digits$}",
+        result.residual_stats.mean,
+        result.residual_stats.std_dev,
+        result.residual_stats.min,
+        result.residual_stats.max
+    );
+
+    match (result.test_r_squared, result.test_rmse) {
+        (Some(test_r_squared), Some(test_rmse)) => {
+            println!("Test R-squared: {:.digits$}", test_r_squared);
+            println!("Test RMSE: {:.error_digits$}", test_rmse);
+        }
+        _ => println!("Test set is empty; skipping out-of-sample evaluation"),
+    }
+
+    println!("{} correlation", result.strength);
+}
+
+// `scaling` is only reported here; the caller has already applied it.
+fn perform_correlation_analysis(
+    train: &Dataset,
+    test: &Dataset,
+    scaling: Scaling,
+    options: &AnalysisOptions,
+) -> Result<Vec<CorrelationResult>, AnalysisError> {
+    println!("\n--- {} Correlation Analyses ---", options.target.label());
+    println!("Feature scaling: {}", scaling.label());
+    println!("Training records: {}, test records: {}", train.len(), test.len());
+    let (weak, strong) = options.strength_thresholds;
+    println!("Strength cutoffs: Weak |r| < {}, Moderate < {}, Strong otherwise", weak, strong);
+
+    let results = compute_correlations(train, test, options);
+    for result in &results {
+        print_correlation_result(result, options.precision);
+    }
+    print_correlation_summary(&results, options.precision);
+
+    Ok(results)
+}
+
+// Orders results by absolute primary correlation, strongest first.
+// Undefined correlations sort last.
+fn ranked_by_correlation(results: &[CorrelationResult]) -> Vec<&CorrelationResult> {
+    let mut ranked: Vec<&CorrelationResult> = results.iter().collect();
+    ranked.sort_by(|a, b| {
+        let (a, b) = (a.primary_correlation(), b.primary_correlation());
+        match (a.is_nan(), b.is_nan()) {
+            (false, false) => b.abs().total_cmp(&a.abs()),
+            (a_nan, b_nan) => a_nan.cmp(&b_nan),
+        }
+    });
+    ranked
+}
+
+fn print_correlation_summary(results: &[CorrelationResult], precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    println!("\n--- Correlation Summary (ranked by |r|) ---");
+    let width = results
+        .iter()
+        .map(|result| result.title.len())
+        .max()
+        .unwrap_or(0)
+        .max("Analysis".len());
+    println!(
+        "{:<width$} {:>12} {:<8} {:>10}  Strength",
+        "Analysis", "Correlation", "Method", "R-squared"
+    );
+    for result in ranked_by_correlation(results) {
+        println!(
+            "{:<width$} {:>12.digits$} {:<8} {:>10.digits$}  {}",
+            result.title,
+            result.primary_correlation(),
+            result.method(),
+            result.r_squared,
+            result.strength
+        );
+    }
+}
+
+fn perform_cross_validation(
+    individuals: &[Individual],
+    k: usize,
+    options: &AnalysisOptions,
+    rng: &mut impl Rng,
+) {
+    println!(
+        "\n--- {}-Fold Cross-Validated R-squared{} ---",
+        k,
+        if options.log_target { " (log scale)" } else { "" }
+    );
+    if individuals.len() < k {
+        println!("Only {} records; skipping cross-validation", individuals.len());
+        return;
+    }
+
+    let digits = options.precision.unwrap_or(4);
+    for field in selected_analyses(options) {
+        let (mean, std_dev) = k_fold_cv(individuals, k, field, options.target, rng);
+        println!("{}: {:.digits$} (std dev {:.digits$})", options.title(field.label()), mean, std_dev);
+    }
+}
+
+// Writes the regression results with full floating-point precision.
+fn write_results_csv(
+    path: &str,
+    results: &[CorrelationResult],
+    delimiter: u8,
+) -> Result<(), AnalysisError> {
+    let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_path(path)?;
+    wtr.write_record(["analysis", "slope", "intercept", "correlation", "r_squared"])?;
+
+    for result in results {
+        wtr.write_record([
+            result.title.clone(),
+            result.slope.to_string(),
+            result.intercept.to_string(),
+            result.correlation.to_string(),
+            result.r_squared.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Writes one row per training record and analysis, in long format, so the
+// residuals can be plotted against the predictor or fitted values.
+fn write_residuals_csv(
+    path: &str,
+    train: &[Individual],
+    results: &[CorrelationResult],
+    options: &AnalysisOptions,
+    delimiter: u8,
+) -> Result<(), AnalysisError> {
+    let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_path(path)?;
+    wtr.write_record(["analysis", "x", options.target.name(), "fitted", "residual"])?;
+
+    // Results come back in selected_analyses() order.
+    for (field, result) in selected_analyses(options).iter().zip(results) {
+        for ind in train {
+            let x = field.get(ind);
+            let y = options.target.get(ind);
+            let fitted = result.model().predict(x);
+            wtr.write_record([
+                result.title.clone(),
+                x.to_string(),
+                y.to_string(),
+                fitted.to_string(),
+                (y - fitted).to_string(),
+            ])?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// The result of the `feature` analysis. Results come back in
+// selected_analyses() order.
+fn result_for_feature<'a>(
+    results: &'a [CorrelationResult],
+    options: &AnalysisOptions,
+    feature: Field,
+) -> &'a CorrelationResult {
+    let index = selected_analyses(options)
+        .iter()
+        .position(|&field| field == feature)
+        .expect("--dump-feature is validated when parsing arguments");
+    &results[index]
+}
+
+// One JSON object per individual with its id, the `feature` value, and the
+// actual and predicted target, numbers at full precision.
+fn predictions_jsonl(
+    individuals: &[Individual],
+    results: &[CorrelationResult],
+    options: &AnalysisOptions,
+    feature: Field,
+) -> Vec<String> {
+    let result = result_for_feature(results, options, feature);
+    let model = result.model();
+    individuals
+        .iter()
+        .map(|ind| {
+            let x = feature.get(ind);
+            format!(
+                "{{\"id\":{},\"analysis\":{},\"x\":{},\"actual\":{},\"predicted\":{}}}",
+                ind.id,
+                json_string(&result.title),
+                json_number(x),
+                json_number(options.target.get(ind)),
+                json_number(model.predict(x))
+            )
+        })
+        .collect()
+}
+
+// Writes the training records of the `feature` analysis as (x, actual_y,
+// predicted_y, residual) for plotting, after a `# analysis:` comment line
+// naming the analysis.
+fn write_predictions_csv(
+    path: &str,
+    train: &[Individual],
+    results: &[CorrelationResult],
+    options: &AnalysisOptions,
+    feature: Field,
+    delimiter: u8,
+) -> Result<(), AnalysisError> {
+    let result = result_for_feature(results, options, feature);
+    let model = result.model();
+
+    let mut file = File::create(path)?;
+    writeln!(file, "# analysis: {}", result.title)?;
+    let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_writer(file);
+    wtr.write_record(["x", "actual_y", "predicted_y", "residual"])?;
+    for ind in train {
+        let x = feature.get(ind);
+        let actual = options.target.get(ind);
+        let predicted = model.predict(x);
+        wtr.write_record([
+            x.to_string(),
+            actual.to_string(),
+            predicted.to_string(),
+            (actual - predicted).to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Writes the individuals with their original ids under the dataset's own
+// column names, so the file can be read back with the same options.
+fn write_sample_csv(path: &str, sample: &[Individual], delimiter: u8) -> Result<(), AnalysisError> {
+    let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_path(path)?;
+    let mut header = vec!["id"];
+    header.extend(NUMERIC_COLUMNS);
+    header.push(FAMILY_INFLUENCE_COLUMN);
+    wtr.write_record(&header)?;
+
+    for ind in sample {
+        wtr.write_record([
+            ind.id.to_string(),
+            ind.age.to_string(),
+            ind.years_of_experience.to_string(),
+            ind.job_satisfaction.to_string(),
+            ind.professional_network_size.to_string(),
+            ind.salary.to_string(),
+            ind.likelihood_to_change_occupation.to_string(),
+            family_influence_label(ind.family_influence).unwrap_or("").to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Minimal JSON encoding helpers; the output is small enough that pulling in
+// serde_json isn't worth it. Non-finite numbers become null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn stats_to_json(stats: &DescriptiveStats) -> String {
+    format!(
+        "{{\"count\":{},\"mean\":{},\"min\":{},\"q1\":{},\"median\":{},\"q3\":{},\"max\":{},\"variance\":{},\"std_dev\":{},\"skewness\":{},\"kurtosis\":{}}}",
+        stats.count,
+        json_number(stats.mean),
+        json_number(stats.min),
+        json_number(stats.q1),
+        json_number(stats.median),
+        json_number(stats.q3),
+        json_number(stats.max),
+        json_number(stats.variance),
+        json_number(stats.std_dev),
+        json_number(stats.skewness),
+        json_number(stats.kurtosis),
+    )
+}
+
+fn correlation_result_to_json(result: &CorrelationResult) -> String {
+    let optional = |value: Option<f64>| value.map_or("null".to_string(), json_number);
+    format!(
+        "{{\"title\":{},\"response\":{},\"log_target\":{},\"slope\":{},\"intercept\":{},\"slope_std_error\":{},\"std_error_of_estimate\":{},\"predict_at\":{},\"prediction\":{},\"prediction_low\":{},\"prediction_high\":{},\"confidence_level\":{},\"slope_ci_low\":{},\"slope_ci_high\":{},\"weighted_slope\":{},\"weighted_intercept\":{},\"covariance\":{},\"correlation\":{},\"method\":{},\"strength\":{},\"bootstrap_ci_low\":{},\"bootstrap_ci_high\":{},\"spearman\":{},\"theil_sen_slope\":{},\"theil_sen_intercept\":{},\"huber_slope\":{},\"huber_intercept\":{},\"huber_iterations\":{},\"kendall\":{},\"r_squared\":{},\"adjusted_r_squared\":{},\"t_statistic\":{},\"p_value\":{},\"rmse\":{},\"mae\":{},\"residuals\":{},\"test_r_squared\":{},\"test_rmse\":{}}}",
+        json_string(&result.title),
+        json_string(result.response),
+        result.log_target,
+        json_number(result.slope),
+        json_number(result.intercept),
+        json_number(result.slope_std_error),
+        json_number(result.std_error_of_estimate),
+        optional(result.prediction.map(|(x0, _, _)| x0)),
+        optional(result.prediction.map(|(_, predicted, _)| predicted)),
+        optional(result.prediction.map(|(_, _, (low, _))| low)),
+        optional(result.prediction.map(|(_, _, (_, high))| high)),
+        json_number(result.confidence_level),
+        json_number(result.slope_ci.0),
+        json_number(result.slope_ci.1),
+        optional(result.weighted_fit.map(|(slope, _)| slope)),
+        optional(result.weighted_fit.map(|(_, intercept)| intercept)),
+        json_number(result.covariance),
+        json_number(result.correlation),
+        json_string(result.method()),
+        json_string(result.strength),
+        optional(result.bootstrap_ci.map(|(low, _)| low)),
+        optional(result.bootstrap_ci.map(|(_, high)| high)),
+        json_number(result.spearman),
+        json_number(result.theil_sen.0),
+        json_number(result.theil_sen.1),
+        json_number(result.huber.0),
+        json_number(result.huber.1),
+        result.huber_iterations,
+        optional(result.kendall),
+        json_number(result.r_squared),
+        json_number(result.adjusted_r_squared),
+        json_number(result.t_statistic),
+        json_number(result.p_value),
+        json_number(result.rmse),
+        json_number(result.mae),
+        stats_to_json(&result.residual_stats),
+        optional(result.test_r_squared),
+        optional(result.test_rmse),
+    )
+}
+
+fn results_to_json(seed: u64, sample: &[Individual], results: &[CorrelationResult]) -> String {
+    let stats: Vec<String> = [
+        ("age", sample.iter().map(|ind| ind.age).collect::<Vec<f64>>()),
+        ("years_of_experience", sample.iter().map(|ind| ind.years_of_experience).collect()),
+        ("salary", sample.iter().map(|ind| ind.salary).collect()),
+    ]
+    .iter()
+    .map(|(name, column)| format!("{}:{}", json_string(name), stats_to_json(&compute_stats(column))))
+    .collect();
+    let analyses: Vec<String> = results.iter().map(correlation_result_to_json).collect();
+
+    format!(
+        "{{\"seed\":{},\"sample_size\":{},\"descriptive_stats\":{{{}}},\"analyses\":[{}]}}",
+        seed,
+        sample.len(),
+        stats.join(","),
+        analyses.join(",")
+    )
+}
+
+// TOML mirror of results_to_json for --toml. TOML has no null, so absent
+// optional values are left out of the document instead.
+#[cfg(feature = "toml")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlReport {
+    seed: u64,
+    sample_size: usize,
+    descriptive_stats: TomlSampleStats,
+    analyses: Vec<TomlAnalysis>,
+}
+
+#[cfg(feature = "toml")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlSampleStats {
+    age: DescriptiveStats,
+    years_of_experience: DescriptiveStats,
+    salary: DescriptiveStats,
+}
+
+// Same keys as correlation_result_to_json, with the residual statistics
+// last because TOML tables must follow plain values.
+#[cfg(feature = "toml")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlAnalysis {
+    title: String,
+    response: String,
+    log_target: bool,
+    slope: f64,
+    intercept: f64,
+    slope_std_error: f64,
+    std_error_of_estimate: f64,
+    predict_at: Option<f64>,
+    prediction: Option<f64>,
+    prediction_low: Option<f64>,
+    prediction_high: Option<f64>,
+    confidence_level: f64,
+    slope_ci_low: f64,
+    slope_ci_high: f64,
+    weighted_slope: Option<f64>,
+    weighted_intercept: Option<f64>,
+    covariance: f64,
+    correlation: f64,
+    method: String,
+    strength: String,
+    bootstrap_ci_low: Option<f64>,
+    bootstrap_ci_high: Option<f64>,
+    spearman: f64,
+    theil_sen_slope: f64,
+    theil_sen_intercept: f64,
+    huber_slope: f64,
+    huber_intercept: f64,
+    huber_iterations: usize,
+    kendall: Option<f64>,
+    r_squared: f64,
+    adjusted_r_squared: f64,
+    t_statistic: f64,
+    p_value: f64,
+    rmse: f64,
+    mae: f64,
+    test_r_squared: Option<f64>,
+    test_rmse: Option<f64>,
+    residuals: DescriptiveStats,
+}
+
+#[cfg(feature = "toml")]
+impl From<&CorrelationResult> for TomlAnalysis {
+    fn from(result: &CorrelationResult) -> Self {
+        TomlAnalysis {
+            title: result.title.clone(),
+            response: result.response.to_string(),
+            log_target: result.log_target,
+            slope: result.slope,
+            intercept: result.intercept,
+            slope_std_error: result.slope_std_error,
+            std_error_of_estimate: result.std_error_of_estimate,
+            predict_at: result.prediction.map(|(x0, _, _)| x0),
+            prediction: result.prediction.map(|(_, predicted, _)| predicted),
+            prediction_low: result.prediction.map(|(_, _, (low, _))| low),
+            prediction_high: result.prediction.map(|(_, _, (_, high))| high),
+            confidence_level: result.confidence_level,
+            slope_ci_low: result.slope_ci.0,
+            slope_ci_high: result.slope_ci.1,
+            weighted_slope: result.weighted_fit.map(|(slope, _)| slope),
+            weighted_intercept: result.weighted_fit.map(|(_, intercept)| intercept),
+            covariance: result.covariance,
+            correlation: result.correlation,
+            method: result.method().to_string(),
+            strength: result.strength.to_string(),
+            bootstrap_ci_low: result.bootstrap_ci.map(|(low, _)| low),
+            bootstrap_ci_high: result.bootstrap_ci.map(|(_, high)| high),
+            spearman: result.spearman,
+            theil_sen_slope: result.theil_sen.0,
+            theil_sen_intercept: result.theil_sen.1,
+            huber_slope: result.huber.0,
+            huber_intercept: result.huber.1,
+            huber_iterations: result.huber_iterations,
+            kendall: result.kendall,
+            r_squared: result.r_squared,
+            adjusted_r_squared: result.adjusted_r_squared,
+            t_statistic: result.t_statistic,
+            p_value: result.p_value,
+            rmse: result.rmse,
+            mae: result.mae,
+            test_r_squared: result.test_r_squared,
+            test_rmse: result.test_rmse,
+            residuals: result.residual_stats.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+fn results_to_toml(seed: u64, sample: &[Individual], results: &[CorrelationResult]) -> String {
+    let column = |field: Field| compute_stats(&sample.iter().map(|ind| field.get(ind)).collect::<Vec<f64>>());
+    let report = TomlReport {
+        seed,
+        sample_size: sample.len(),
+        descriptive_stats: TomlSampleStats {
+            age: column(Field::Age),
+            years_of_experience: column(Field::Experience),
+            salary: column(Field::Salary),
+        },
+        analyses: results.iter().map(TomlAnalysis::from).collect(),
+    };
+    toml::to_string(&report).expect("the TOML report only holds plain values")
+}
+
+// Renders the sample verification statistics and the salary analyses as a
+// Markdown document, from the same numbers printed to stdout.
+fn markdown_report(seed: u64, sample: &[Individual], results: &[CorrelationResult]) -> String {
+    let mut lines = vec![
+        "# Salary Analysis Report".to_string(),
+        String::new(),
+        format!("Random seed: {}, sample size: {}", seed, sample.len()),
+        String::new(),
+        "## Descriptive Statistics".to_string(),
+        String::new(),
+        "| Column | Count | Mean | Std Dev | Min | Q1 | Median | Q3 | Max |".to_string(),
+        "|---|---:|---:|---:|---:|---:|---:|---:|---:|".to_string(),
+    ];
+
+    for field in [Field::Age, Field::Experience, Field::Salary] {
+        let stats = compute_stats(&sample.iter().map(|ind| field.get(ind)).collect::<Vec<f64>>());
+        lines.push(format!(
+            "| {} | {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} |",
+            field.label(),
+            stats.count,
+            stats.mean,
+            stats.std_dev,
+            stats.min,
+            stats.q1,
+            stats.median,
+            stats.q3,
+            stats.max
+        ));
+    }
+
+    lines.extend([
+        String::new(),
+        "## Family Influence Distribution".to_string(),
+        String::new(),
+        "| Level | Share |".to_string(),
+        "|---|---:|".to_string(),
+    ]);
+    for (label, share) in FAMILY_INFLUENCE_LEVELS.iter().zip(family_influence_shares(sample)) {
+        lines.push(format!("| {} | {:.2}% |", label, share * 100.0));
+    }
+
+    lines.extend([
+        String::new(),
+        "## Correlation Analyses".to_string(),
+        String::new(),
+        "| Analysis | Slope | Intercept | Pearson | Spearman | R² | p-value | Strength |".to_string(),
+        "|---|---:|---:|---:|---:|---:|---:|---|".to_string(),
+    ]);
+    for result in results {
+        lines.push(format!(
+            "| {} | {:.4} | {:.4} | {:.4} | {:.4} | {:.4} | {:.4} | {} |",
+            result.title,
+            result.slope,
+            result.intercept,
+            result.correlation,
+            result.spearman,
+            result.r_squared,
+            result.p_value,
+            result.strength
+        ));
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+// Solves `a * x = b` by Gaussian elimination with partial pivoting. Returns
+// None when the matrix is (numerically) singular.
+fn solve_linear_system(a: Vec<Vec<f64>>, b: Vec<f64>) -> Option<Vec<f64>> {
+    solve_linear_system_with_pivot_ratio(a, b).map(|(x, _)| x)
+}
+
+// As solve_linear_system, also returning max |pivot| / min |pivot|: a cheap
+// condition estimate where large values mean the solution is unreliable.
+fn solve_linear_system_with_pivot_ratio(
+    mut a: Vec<Vec<f64>>,
+    mut b: Vec<f64>,
+) -> Option<(Vec<f64>, f64)> {
+    let n = b.len();
+    let scale = a
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0.0_f64, |acc, v| acc.max(v.abs()));
+    let tolerance = 1e-12 * scale.max(1.0);
+
+    for col in 0..n {
+        let pivot_row = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot_row][col].abs() < tolerance {
+            return None;
+        }
+        a.swap(col, pivot_row);
+        b.swap(col, pivot_row);
+
+        let pivot = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot[col];
+            for (value, pivot_value) in a[row][col..].iter_mut().zip(&pivot[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+
+    let pivots = (0..n).map(|i| a[i][i].abs());
+    let max_pivot = pivots.clone().fold(0.0_f64, f64::max);
+    let min_pivot = pivots.fold(f64::INFINITY, f64::min);
+
+    Some((x, max_pivot / min_pivot))
+}
+
+// Normal equations (X^T X, X^T y) for a design matrix with an intercept
+// column followed by one column per feature.
+fn normal_equations(features: &[Vec<f64>], y: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+    for column in features {
+        assert_eq!(column.len(), y.len(), "Input vectors must be of equal length");
+    }
+
+    // Design matrix row i is [1, x_1i, ..., x_pi].
+    let p = features.len() + 1;
+    let mut xtx = vec![vec![0.0; p]; p];
+    let mut xty = vec![0.0; p];
+    let mut row = vec![1.0; p];
+
+    for i in 0..y.len() {
+        for (j, column) in features.iter().enumerate() {
+            row[j + 1] = column[i];
+        }
+        for a in 0..p {
+            xty[a] += row[a] * y[i];
+            for b in 0..p {
+                xtx[a][b] += row[a] * row[b];
+            }
+        }
+    }
+
+    (xtx, xty)
+}
+
+// Printed in place of a fit whose normal equations could not be solved.
+const SINGULAR_DESIGN_MESSAGE: &str = "skipped: design matrix is singular (constant or collinear features, or n <= p)";
+
+// Ordinary least squares with an intercept. `features` holds one column per
+// predictor; the result is [intercept, coefficient_1, ..., coefficient_p], or
+// None when the design matrix is singular.
+fn multiple_linear_regression(features: &[Vec<f64>], y: &[f64]) -> Option<Vec<f64>> {
+    let (xtx, xty) = normal_equations(features, y);
+    solve_linear_system(xtx, xty)
+}
+
+// Ridge regression: least squares with `lambda` added to the diagonal of
+// X^T X for every coefficient except the intercept, which stays unpenalized.
+// Returns [intercept, coefficient_1, ..., coefficient_p] like
+// multiple_linear_regression, which it matches at lambda = 0.
+fn ridge_regression(features: &[Vec<f64>], y: &[f64], lambda: f64) -> Option<Vec<f64>> {
+    assert!(lambda >= 0.0, "Ridge lambda must be non-negative");
+    let (mut xtx, xty) = normal_equations(features, y);
+    for (j, row) in xtx.iter_mut().enumerate().skip(1) {
+        row[j] += lambda;
+    }
+    solve_linear_system(xtx, xty)
+}
+
+// Least-squares polynomial fit y = c_0 + c_1 x + ... + c_d x^d via the
+// Vandermonde normal equations. Returns [c_0, ..., c_d], or None when the
+// system is singular (too few distinct x values for the degree); warns when
+// it is ill-conditioned, which happens quickly as the degree grows.
+fn polynomial_regression(x: &[f64], y: &[f64], degree: usize) -> Option<Vec<f64>> {
+    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
+    let powers: Vec<Vec<f64>> = (1..=degree)
+        .map(|power| x.iter().map(|xi| xi.powi(power as i32)).collect())
+        .collect();
+
+    let (xtx, xty) = normal_equations(&powers, y);
+    let (coefficients, pivot_ratio) = solve_linear_system_with_pivot_ratio(xtx, xty)?;
+    if pivot_ratio > 1e12 {
+        eprintln!(
+            "Warning: degree-{} fit is ill-conditioned (pivot ratio {:.2e}); coefficients may be inaccurate",
+            degree, pivot_ratio
+        );
+    }
+
+    Some(coefficients)
+}
+
+// Number of distinct finite values; a degree-d polynomial needs more than d.
+fn distinct_finite_count(data: &[f64]) -> usize {
+    let mut values: Vec<f64> = data.iter().cloned().filter(|v| v.is_finite()).collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    values.dedup();
+    values.len()
+}
+
+// Evaluates c_0 + c_1 x + ... + c_d x^d with Horner's rule.
+fn evaluate_polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+// Fits salary against years of experience with a polynomial of the given
+// degree and compares its R-squared to the straight-line fit.
+fn perform_polynomial_regression(individuals: &[Individual], degree: usize, precision: Option<usize>) {
+    let coefficient_digits = precision.unwrap_or(6);
+    let digits = precision.unwrap_or(4);
+    let x: Vec<f64> = individuals.iter().map(|ind| ind.years_of_experience).collect();
+    let y: Vec<f64> = individuals.iter().map(|ind| ind.salary).collect();
+
+    println!("\n--- Polynomial Regression (Salary vs Years of Experience, degree {}) ---", degree);
+    let distinct = distinct_finite_count(&x);
+    if degree >= distinct {
+        eprintln!(
+            "Warning: degree {} needs more than {} distinct years of experience; got {}",
+            degree, degree, distinct
+        );
+        println!("skipped: too few distinct x values for degree {}", degree);
+        return;
+    }
+    let Some(coefficients) = polynomial_regression(&x, &y, degree) else {
+        println!("{}", SINGULAR_DESIGN_MESSAGE);
+        return;
+    };
+    let predicted: Vec<f64> = x.iter().map(|xi| evaluate_polynomial(&coefficients, *xi)).collect();
+    let (_, _, _, linear_r_squared) = calculate_linear_regression(&x, &y);
+
+    for (power, coefficient) in coefficients.iter().enumerate() {
+        println!("x^{}: {:.coefficient_digits$}", power, coefficient);
+    }
+    println!("R-squared: {:.digits$}", r_squared_from_predictions(&y, &predicted));
+    println!("Linear R-squared: {:.digits$}", linear_r_squared);
+}
+
+// Penalizes R-squared for the number of predictors `p` given `n`
+// observations: 1 - (1 - R^2)(n - 1)/(n - p - 1).
+fn adjusted_r_squared(r_squared: f64, n: usize, p: usize) -> f64 {
+    if n <= p + 1 {
+        eprintln!(
+            "Warning: adjusted R-squared needs more than {} observations for {} predictors; got {}",
+            p + 1,
+            p,
+            n
+        );
+        return f64::NAN;
+    }
+    1.0 - (1.0 - r_squared) * (n - 1) as f64 / (n - p - 1) as f64
+}
+
+// Fits multiple_linear_regression and returns (coefficients, in-sample
+// R-squared), or None when the fit is singular.
+fn fit_multiple_regression(columns: &[Vec<f64>], y: &[f64]) -> Option<(Vec<f64>, f64)> {
+    let coefficients = multiple_linear_regression(columns, y)?;
+
+    let predicted: Vec<f64> = (0..y.len())
+        .map(|i| {
+            coefficients[0]
+                + columns
+                    .iter()
+                    .zip(&coefficients[1..])
+                    .map(|(column, b)| b * column[i])
+                    .sum::<f64>()
+        })
+        .collect();
+
+    let r_squared = r_squared_from_predictions(y, &predicted);
+    Some((coefficients, r_squared))
+}
+
+// Indicator columns for each family influence level present in the sample
+// except the lowest, which becomes the reference level. Returns the
+// reference's label and (level label, column) per remaining level; a
+// missing influence is NaN in every column rather than the reference.
+fn influence_dummies(individuals: &[Individual]) -> (&'static str, Vec<(&'static str, Vec<f64>)>) {
+    let present: Vec<usize> = (0..FAMILY_INFLUENCE_LEVELS.len())
+        .filter(|&level| individuals.iter().any(|ind| ind.family_influence == level as f64))
+        .collect();
+    let Some((&reference, others)) = present.split_first() else {
+        return (FAMILY_INFLUENCE_LEVELS[0], Vec::new());
+    };
+
+    let dummies = others
+        .iter()
+        .map(|&level| {
+            let column = individuals
+                .iter()
+                .map(|ind| match ind.family_influence {
+                    value if value.is_nan() => f64::NAN,
+                    value if value == level as f64 => 1.0,
+                    _ => 0.0,
+                })
+                .collect();
+            (FAMILY_INFLUENCE_LEVELS[level], column)
+        })
+        .collect();
+    (FAMILY_INFLUENCE_LEVELS[reference], dummies)
+}
+
+// Removes predictors whose non-missing values are all equal, such as the
+// family influence column after `--filter influence==2`, warning for each;
+// a constant column duplicates the intercept and makes X^T X singular.
+fn drop_constant_columns(features: &mut Vec<(&str, Vec<f64>)>) {
+    features.retain(|(name, column)| {
+        let min = column.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = column.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if min == max {
+            eprintln!("Warning: {} has zero variance in this sample; leaving it out of the multiple regression", name);
+            return false;
+        }
+        true
+    });
+}
+
+// With `interaction`, also fits the model plus an experience x network size
+// column and reports whether it raises adjusted R-squared. With
+// `dummy_influence`, family influence enters as indicator columns from
+// influence_dummies instead of its ordinal code.
+fn perform_multiple_regression(
+    individuals: &[Individual],
+    interaction: bool,
+    ridge_lambda: Option<f64>,
+    dummy_influence: bool,
+    precision: Option<usize>,
+) {
+    let digits = precision.unwrap_or(4);
+    let (reference, dummies) = influence_dummies(individuals);
+    let dummy_names: Vec<String> = dummies
+        .iter()
+        .map(|(level, _)| format!("Family Influence: {}", level))
+        .collect();
+
+    let column = |field: Field| -> (&str, Vec<f64>) {
+        (field.label(), individuals.iter().map(|ind| field.get(ind)).collect())
+    };
+    let mut features: Vec<(&str, Vec<f64>)> =
+        [Field::Age, Field::Experience, Field::Satisfaction, Field::Network].map(column).to_vec();
+    if dummy_influence {
+        features.extend(dummy_names.iter().map(String::as_str).zip(dummies.into_iter().map(|(_, column)| column)));
+    } else {
+        features.push(column(Field::Influence));
+    }
+    features.push(column(Field::Likelihood));
+    drop_constant_columns(&mut features);
+    let salaries = column(Field::Salary).1;
+
+    let mut columns: Vec<Vec<f64>> = features.iter().map(|(_, column)| column.clone()).collect();
+    println!("\n--- Multiple Linear Regression (Salary) ---");
+    let Some((coefficients, r_squared)) = fit_multiple_regression(&columns, &salaries) else {
+        println!("{}", SINGULAR_DESIGN_MESSAGE);
+        return;
+    };
+    let adjusted = adjusted_r_squared(r_squared, salaries.len(), columns.len());
+
+    println!("Intercept: {:.digits$}", coefficients[0]);
+    for ((name, _), coefficient) in features.iter().zip(&coefficients[1..]) {
+        println!("{}: {:.digits$}", name, coefficient);
+    }
+    if dummy_influence {
+        println!("(Family Influence coefficients are relative to the {} level)", reference);
+    }
+    println!("R-squared: {:.digits$}, Adjusted R-squared: {:.digits$}", r_squared, adjusted);
+
+    if let Some(lambda) = ridge_lambda {
+        print_ridge_path(&features, &salaries, lambda, precision);
+    }
+
+    if interaction {
+        columns.push(
+            individuals
+                .iter()
+                .map(|ind| ind.years_of_experience * ind.professional_network_size)
+                .collect(),
+        );
+        println!("\nWith Experience x Network Size interaction:");
+        let Some((coefficients, r_squared)) = fit_multiple_regression(&columns, &salaries) else {
+            println!("{}", SINGULAR_DESIGN_MESSAGE);
+            return;
+        };
+        let interaction_adjusted = adjusted_r_squared(r_squared, salaries.len(), columns.len());
+
+        println!("Interaction coefficient: {:.digits$}", coefficients[columns.len()]);
+        println!(
+            "R-squared: {:.digits$}, Adjusted R-squared: {:.digits$} ({} adjusted R-squared by {:.digits$})",
+            r_squared,
+            interaction_adjusted,
+            if interaction_adjusted > adjusted { "improves" } else { "does not improve" },
+            (interaction_adjusted - adjusted).abs()
+        );
+    }
+}
+
+// Ridge coefficients at `lambda` next to OLS, then the L2 norm of the
+// (non-intercept) coefficients over a grid around lambda to show shrinkage.
+fn print_ridge_path(features: &[(&str, Vec<f64>)], y: &[f64], lambda: f64, precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    let columns: Vec<Vec<f64>> = features.iter().map(|(_, column)| column.clone()).collect();
+    println!("\nRidge regression (lambda = {}, intercept unpenalized):", lambda);
+    let (Some(ols), Some(ridge)) = (ridge_regression(&columns, y, 0.0), ridge_regression(&columns, y, lambda)) else {
+        println!("{}", SINGULAR_DESIGN_MESSAGE);
+        return;
+    };
+    println!("Intercept: {:.digits$} (OLS {:.digits$})", ridge[0], ols[0]);
+    for ((name, _), (r, o)) in features.iter().zip(ridge[1..].iter().zip(&ols[1..])) {
+        println!("{}: {:.digits$} (OLS {:.digits$})", name, r, o);
+    }
+    let predicted: Vec<f64> = (0..y.len())
+        .map(|i| ridge[0] + columns.iter().zip(&ridge[1..]).map(|(column, b)| b * column[i]).sum::<f64>())
+        .collect();
+    println!("R-squared: {:.digits$}", r_squared_from_predictions(y, &predicted));
+
+    println!("Coefficient norm as lambda grows:");
+    for scale in [0.0, 0.1, 1.0, 10.0, 100.0] {
+        let Some(coefficients) = ridge_regression(&columns, y, lambda * scale) else {
+            println!("lambda = {:<12} {}", lambda * scale, SINGULAR_DESIGN_MESSAGE);
+            continue;
+        };
+        let norm = coefficients[1..].iter().map(|b| b * b).sum::<f64>().sqrt();
+        println!("lambda = {:<12} ||b|| = {:.digits$}", lambda * scale, norm);
+    }
+}
+
+// Fits salary on `base` and on `base` plus `added`, returning (base R^2,
+// full R^2, F, p-value) for the partial F-test of the added predictor:
+// F = (R^2_full - R^2_base) / ((1 - R^2_full) / (n - k_full - 1)).
+// None when n <= k_full + 1 leaves no residual degrees of freedom or either
+// fit is singular.
+fn nested_model_comparison(sample: &Dataset, base: &[Field], added: Field) -> Option<(f64, f64, f64, f64)> {
+    let salaries = sample.column(Field::Salary);
+    if salaries.len() <= base.len() + 2 {
+        return None;
+    }
+    let mut columns: Vec<Vec<f64>> = base.iter().map(|&field| sample.column(field)).collect();
+    let (_, base_r_squared) = fit_multiple_regression(&columns, &salaries)?;
+    columns.push(sample.column(added));
+    let (_, full_r_squared) = fit_multiple_regression(&columns, &salaries)?;
+
+    let df_residual = (salaries.len() - columns.len() - 1) as f64;
+    let f = (full_r_squared - base_r_squared) / ((1.0 - full_r_squared) / df_residual);
+    Some((base_r_squared, full_r_squared, f, f_test_p_value(f, 1.0, df_residual)))
+}
+
+fn print_nested_model_comparison(sample: &Dataset, base: &[Field], added: Field, precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    println!("\n--- Nested Model Comparison (Salary) ---");
+    let Some((base_r_squared, full_r_squared, f, p_value)) = nested_model_comparison(sample, base, added) else {
+        if sample.len() <= base.len() + 2 {
+            println!(
+                "skipped: need more than {} observations for {} predictors; got {}",
+                base.len() + 2,
+                base.len() + 1,
+                sample.len()
+            );
+        } else {
+            println!("{}", SINGULAR_DESIGN_MESSAGE);
+        }
+        return;
+    };
+    let base_labels: Vec<&str> = base.iter().map(|field| field.label()).collect();
+    println!("Base model: {} (R-squared {:.digits$})", base_labels.join(", "), base_r_squared);
+    println!(
+        "Adding {} {} R-squared by {:.digits$} (F = {:.digits$}, p = {:.digits$})",
+        added.label(),
+        if full_r_squared >= base_r_squared { "improves" } else { "lowers" },
+        (full_r_squared - base_r_squared).abs(),
+        f,
+        p_value
+    );
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+// Logistic regression fit by batch gradient descent on the mean log loss.
+// `features` holds one column per predictor (standardize them first); the
+// result is [intercept, weight_1, ..., weight_p].
+fn logistic_regression(features: &[Vec<f64>], y: &[f64], lr: f64, epochs: usize) -> Vec<f64> {
+    for column in features {
+        assert_eq!(column.len(), y.len(), "Input vectors must be of equal length");
+    }
+    let n = y.len() as f64;
+    let mut weights = vec![0.0; features.len() + 1];
+
+    for _ in 0..epochs {
+        let mut gradient = vec![0.0; weights.len()];
+        for i in 0..y.len() {
+            let z = weights[0]
+                + features
+                    .iter()
+                    .zip(&weights[1..])
+                    .map(|(column, w)| w * column[i])
+                    .sum::<f64>();
+            let error = sigmoid(z) - y[i];
+            gradient[0] += error;
+            for (g, column) in gradient[1..].iter_mut().zip(features) {
+                *g += error * column[i];
+            }
+        }
+        for (w, g) in weights.iter_mut().zip(&gradient) {
+            *w -= lr * g / n;
+        }
+    }
+
+    weights
+}
+
+fn perform_logistic_regression(individuals: &[Individual], precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    let features = [
+        Field::Age,
+        Field::Experience,
+        Field::Satisfaction,
+        Field::Network,
+        Field::Influence,
+        Field::Salary,
+    ];
+    let columns: Vec<Vec<f64>> = features
+        .iter()
+        .map(|field| zscore_normalize(&individuals.iter().map(|ind| field.get(ind)).collect::<Vec<f64>>()))
+        .collect();
+    let y: Vec<f64> = individuals
+        .iter()
+        .map(|ind| ind.likelihood_to_change_occupation)
+        .collect();
+
+    let weights = logistic_regression(&columns, &y, 0.1, 1_000);
+
+    let correct = (0..y.len())
+        .filter(|&i| {
+            let z = weights[0]
+                + columns
+                    .iter()
+                    .zip(&weights[1..])
+                    .map(|(column, w)| w * column[i])
+                    .sum::<f64>();
+            let predicted = if sigmoid(z) >= 0.5 { 1.0 } else { 0.0 };
+            predicted == y[i]
+        })
+        .count();
+
+    println!("\n--- Logistic Regression (Likelihood to Change Occupation) ---");
+    println!("Features standardized with z-scores");
+    println!("Intercept: {:.digits$}", weights[0]);
+    for (field, weight) in features.iter().zip(&weights[1..]) {
+        println!("{}: {:.digits$}", field.label(), weight);
+    }
+    println!(
+        "Accuracy (threshold 0.5): {:.2}%",
+        correct as f64 / y.len() as f64 * 100.0
+    );
+}
+
+// Counts values into `bins` equal-width buckets spanning [min, max]; the max
+// lands in the last bucket. Returns (lower edge, width, counts), with all
+// values in the first bucket and a zero width when every value is equal.
+fn histogram(data: &[f64], bins: usize) -> (f64, f64, Vec<usize>) {
+    assert!(bins > 0, "Histogram needs at least one bin");
+    let mut counts = vec![0; bins];
+    if data.is_empty() {
+        return (f64::NAN, f64::NAN, counts);
+    }
+
+    let min = data.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / bins as f64;
+
+    for &value in data {
+        let bin = if width > 0.0 {
+            (((value - min) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+
+    (min, width, counts)
+}
+
+// Prints one ASCII bar per histogram bin, scaled so the fullest bin is 40
+// characters wide.
+fn print_histogram(data: &[f64], bins: usize) {
+    const BAR_WIDTH: usize = 40;
+    let (min, width, counts) = histogram(data, bins);
+    let largest = counts.iter().cloned().max().unwrap_or(0).max(1);
+
+    for (i, count) in counts.iter().enumerate() {
+        let lower = min + width * i as f64;
+        let bar = "#".repeat(count * BAR_WIDTH / largest);
+        println!("{:>12.2} - {:>12.2} | {:<40} {}", lower, lower + width, bar, count);
+    }
+}
+
+fn print_sample_verification(sample: &[Individual], bins: usize, precision: Option<usize>) {
+    println!("\n--- Random Sample Verification ---");
+    println!("Total records in sample: {}", sample.len());
+
+    let ages: Vec<f64> = sample.iter().map(|ind| ind.age).collect();
+    let experiences: Vec<f64> = sample.iter().map(|ind| ind.years_of_experience).collect();
+    let salaries: Vec<f64> = sample.iter().map(|ind| ind.salary).collect();
+    
+    println!("\nAge Distribution:");
+    print_stats(&ages, precision);
+    print_histogram(&ages, bins);
+
+    println!("\nYears of Experience Distribution:");
+    print_stats(&experiences, precision);
+
+    println!("\nSalary Distribution:");
+    print_stats(&salaries, precision);
+    print_histogram(&salaries, bins);
+
+    println!("\nFamily Influence Distribution:");
+    for (label, share) in FAMILY_INFLUENCE_LEVELS.iter().zip(family_influence_shares(sample)) {
+        println!("{}: {:.2}%", label, share * 100.0);
+    }
+    let influences: Vec<f64> = sample.iter().map(|ind| ind.family_influence).collect();
+    if let Some(label) = mode(&influences).and_then(family_influence_label) {
+        println!("Most common influence: {}", label);
+    }
+
+    println!("\nOutliers (1.5 x IQR rule):");
+    let digits = precision.unwrap_or(2);
+    for field in Field::NUMERIC {
+        let values: Vec<f64> = sample.iter().map(|ind| field.get(ind)).collect();
+        if let Some((lower, upper)) = iqr_fences(&values) {
+            println!(
+                "{}: {} outliers (fences: {:.digits$} to {:.digits$})",
+                field.short_label(),
+                outliers_iqr(&values).len(),
+                lower,
+                upper
+            );
+        }
+    }
+
+    println!("\nFirst 10 Records (Original IDs):");
+    for ind in sample.iter().take(10) {
+        println!("ID: {}, Age: {}, Salary: {}", ind.id, ind.age, ind.salary);
+    }
+
+    println!("\nLast 10 Records (Original IDs):");
+    for ind in sample.iter().rev().take(10) {
+        println!("ID: {}, Age: {}, Salary: {}", ind.id, ind.age, ind.salary);
+    }
+}
+
+// The first n individuals as aligned table lines (header first), with family
+// influence shown as its label, for checking that the columns parsed right.
+fn head_table(individuals: &[Individual], n: usize) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:>6} {:>6} {:>10} {:>12} {:>8} {:>10} {:>12} {:>10}",
+        "ID", "Age", "Experience", "Satisfaction", "Network", "Influence", "Salary", "Likelihood"
+    )];
+    for ind in individuals.iter().take(n) {
+        lines.push(format!(
+            "{:>6} {:>6} {:>10} {:>12} {:>8} {:>10} {:>12} {:>10}",
+            ind.id,
+            ind.age,
+            ind.years_of_experience,
+            ind.job_satisfaction,
+            ind.professional_network_size,
+            family_influence_label(ind.family_influence).unwrap_or("?"),
+            ind.salary,
+            ind.likelihood_to_change_occupation
+        ));
+    }
+    lines
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+// Lloyd's k-means with Euclidean distance. Centroids start at k distinct
+// random points; a centroid that loses all its points is moved to a random
+// point. Returns (per-point cluster assignments, centroids).
+fn kmeans(
+    points: &[Vec<f64>],
+    k: usize,
+    max_iters: usize,
+    rng: &mut impl Rng,
+) -> (Vec<usize>, Vec<Vec<f64>>) {
+    assert!(k >= 1 && k <= points.len(), "k must be between 1 and the number of points");
+    let dims = points[0].len();
+
+    let mut centroids: Vec<Vec<f64>> = rand::seq::index::sample(rng, points.len(), k)
+        .into_iter()
+        .map(|i| points[i].clone())
+        .collect();
+    let mut assignments = vec![usize::MAX; points.len()];
+
+    for _ in 0..max_iters {
+        let mut changed = false;
+        for (point, assignment) in points.iter().zip(assignments.iter_mut()) {
+            let nearest = (0..k)
+                .min_by(|&a, &b| {
+                    squared_distance(point, &centroids[a])
+                        .total_cmp(&squared_distance(point, &centroids[b]))
+                })
+                .unwrap_or(0);
+            if *assignment != nearest {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![vec![0.0; dims]; k];
+        let mut counts = vec![0usize; k];
+        for (point, &cluster) in points.iter().zip(&assignments) {
+            counts[cluster] += 1;
+            for (sum, value) in sums[cluster].iter_mut().zip(point) {
+                *sum += value;
+            }
+        }
+
+        for cluster in 0..k {
+            if counts[cluster] == 0 {
+                centroids[cluster] = points[rng.gen_range(0..points.len())].clone();
+            } else {
+                centroids[cluster] = sums[cluster]
+                    .iter()
+                    .map(|sum| sum / counts[cluster] as f64)
+                    .collect();
+            }
+        }
+    }
+
+    (assignments, centroids)
+}
+
+// Clusters individuals on their min-max scaled predictor fields and reports
+// the size and mean salary of each cluster.
+fn perform_clustering(individuals: &[Individual], k: usize, precision: Option<usize>, rng: &mut impl Rng) {
+    let digits = precision.unwrap_or(2);
+    let scaled = scale_features(individuals, Scaling::MinMax);
+    let points: Vec<Vec<f64>> = scaled
+        .iter()
+        .map(|ind| {
+            vec![
+                ind.age,
+                ind.years_of_experience,
+                ind.job_satisfaction,
+                ind.professional_network_size,
+                ind.family_influence,
+                ind.likelihood_to_change_occupation,
+            ]
+        })
+        .collect();
+
+    println!("\n--- K-Means Clustering (k = {}) ---", k);
+    if k == 0 || k > points.len() {
+        println!("Cannot form {} clusters from {} individuals", k, points.len());
+        return;
+    }
+
+    let (assignments, _) = kmeans(&points, k, 100, rng);
+
+    for cluster in 0..k {
+        let salaries: Vec<f64> = individuals
+            .iter()
+            .zip(&assignments)
+            .filter(|(_, &assigned)| assigned == cluster)
+            .map(|(ind, _)| ind.salary)
+            .collect();
+        let stats = compute_stats(&salaries);
+        println!(
+            "Cluster {}: {} individuals, mean salary {:.digits$}",
+            cluster + 1,
+            stats.count,
+            stats.mean
+        );
+    }
+}
+
+// Salary summary for each family influence level.
+fn print_salary_by_family_influence(sample: &[Individual], precision: Option<usize>) {
+    let digits = precision.unwrap_or(2);
+    println!("\n--- Salary by Family Influence ---");
+    for (code, label) in FAMILY_INFLUENCE_LEVELS.iter().enumerate() {
+        let salaries: Vec<f64> = sample
+            .iter()
+            .filter(|ind| ind.family_influence == code as f64)
+            .map(|ind| ind.salary)
+            .collect();
+
+        if salaries.is_empty() {
+            println!("{}: (no records)", label);
+            continue;
+        }
+        let stats = compute_stats(&salaries);
+        println!(
+            "{}: count {}, mean salary {:.digits$}, median salary {:.digits$}",
+            label, stats.count, stats.mean, stats.median
+        );
+    }
+}
+
+// Splits the sample into contiguous age bands of `width` years starting at
+// the youngest age, up to the band containing the oldest. Returns
+// (band start, count, mean salary) per band; empty bands have a NaN mean.
+// Individuals without a finite age belong to no band and are left out.
+fn age_bands(sample: &[Individual], width: f64) -> Vec<(f64, usize, f64)> {
+    assert!(width > 0.0, "Age band width must be positive");
+    let aged: Vec<&Individual> = sample.iter().filter(|ind| ind.age.is_finite()).collect();
+    if aged.is_empty() {
+        return Vec::new();
+    }
+
+    let min = aged.iter().map(|ind| ind.age).fold(f64::INFINITY, f64::min);
+    let max = aged.iter().map(|ind| ind.age).fold(f64::NEG_INFINITY, f64::max);
+    let bands = ((max - min) / width).floor() as usize + 1;
+
+    let mut counts = vec![0usize; bands];
+    let mut salary_sums = vec![0.0; bands];
+    for ind in aged {
+        let band = (((ind.age - min) / width) as usize).min(bands - 1);
+        counts[band] += 1;
+        salary_sums[band] += ind.salary;
+    }
+
+    (0..bands)
+        .map(|band| {
+            let mean_salary = if counts[band] > 0 {
+                salary_sums[band] / counts[band] as f64
+            } else {
+                f64::NAN
+            };
+            (min + band as f64 * width, counts[band], mean_salary)
+        })
+        .collect()
+}
+
+// One line per age band, as printed by print_age_bands.
+fn age_band_lines(sample: &[Individual], width: f64, precision: Option<usize>) -> Vec<String> {
+    let digits = precision.unwrap_or(2);
+    age_bands(sample, width)
+        .into_iter()
+        .map(|(start, count, mean_salary)| {
+            if count == 0 {
+                format!("{}-{}: count 0", start, start + width)
+            } else {
+                format!("{}-{}: count {}, mean salary {:.digits$}", start, start + width, count, mean_salary)
+            }
+        })
+        .collect()
+}
+
+fn print_age_bands(sample: &[Individual], width: f64, precision: Option<usize>) {
+    println!("\n--- Records by Age Band ({} years) ---", width);
+    for line in age_band_lines(sample, width, precision) {
+        println!("{}", line);
+    }
+}
+
+// Centered moving average: point i averages indices i - (window - 1) / 2
+// through i + window / 2, so an even window leans one point forward. Near
+// the edges the window shrinks to the points that exist.
+fn moving_average(data: &[f64], window: usize) -> Vec<f64> {
+    assert!(window >= 1, "Moving average window must be at least 1");
+    (0..data.len())
+        .map(|i| {
+            let start = i.saturating_sub((window - 1) / 2);
+            let end = (i + window / 2 + 1).min(data.len());
+            data[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect()
+}
+
+// Mean salary at each distinct age, in age order, with that series smoothed
+// by moving_average: (age, mean salary, smoothed mean salary).
+fn salary_trend_by_age(sample: &[Individual], window: usize) -> Vec<(f64, f64, f64)> {
+    let mut sorted: Vec<&Individual> = sample.iter().filter(|ind| !ind.age.is_nan()).collect();
+    sorted.sort_by(|a, b| a.age.total_cmp(&b.age));
+
+    let mut ages = Vec::new();
+    let mut means = Vec::new();
+    for group in sorted.chunk_by(|a, b| a.age == b.age) {
+        ages.push(group[0].age);
+        means.push(group.iter().map(|ind| ind.salary).sum::<f64>() / group.len() as f64);
+    }
+    let smoothed = moving_average(&means, window);
+    ages.into_iter()
+        .zip(means)
+        .zip(smoothed)
+        .map(|((age, mean), smooth)| (age, mean, smooth))
+        .collect()
+}
+
+fn print_salary_trend(sample: &[Individual], window: usize, precision: Option<usize>) {
+    let digits = precision.unwrap_or(2);
+    println!("\n--- Mean Salary by Age (moving average over {} ages) ---", window);
+    println!("{:>6} {:>14} {:>14}", "Age", "Mean Salary", "Smoothed");
+    for (age, mean, smoothed) in salary_trend_by_age(sample, window) {
+        println!("{:>6} {:>14.digits$} {:>14.digits$}", age, mean, smoothed);
+    }
+}
+
+// Splits the individuals with finite `field` and salary into `bins` quantile
+// groups of `field`; values on a cut point go to the lower group, so heavy
+// ties can leave fewer, uneven groups. Returns ((min, max) of `field`,
+// salaries) per non-empty group in ascending order.
+fn salary_by_quantile_bins(sample: &[Individual], field: Field, bins: usize) -> Vec<((f64, f64), Vec<f64>)> {
+    assert!(bins >= 1, "Need at least one bin");
+    let mut pairs: Vec<(f64, f64)> = sample
+        .iter()
+        .map(|ind| (field.get(ind), ind.salary))
+        .filter(|(x, salary)| x.is_finite() && salary.is_finite())
+        .collect();
+    if pairs.is_empty() {
+        return Vec::new();
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let sorted: Vec<f64> = pairs.iter().map(|(x, _)| *x).collect();
+    let cuts: Vec<f64> = (1..bins).map(|j| percentile(&sorted, j as f64 / bins as f64)).collect();
+
+    let mut groups: Vec<((f64, f64), Vec<f64>)> = Vec::new();
+    let mut current = None;
+    for (x, salary) in pairs {
+        let bin = cuts.iter().filter(|&&cut| cut < x).count();
+        if current != Some(bin) {
+            current = Some(bin);
+            groups.push(((x, x), Vec::new()));
+        }
+        let (range, salaries) = groups.last_mut().expect("a group was just pushed");
+        range.1 = x;
+        salaries.push(salary);
+    }
+    groups
+}
+
+fn print_anova_by_bins(sample: &[Individual], field: Field, bins: usize, precision: Option<usize>) {
+    let digits = precision.unwrap_or(4);
+    let salary_digits = precision.unwrap_or(2);
+    let groups = salary_by_quantile_bins(sample, field, bins);
+    println!("\n--- Salary by {} Quantile Group ({} bins) ---", field.label(), bins);
+    for ((low, high), salaries) in &groups {
+        println!(
+            "{} to {}: count {}, mean salary {:.salary_digits$}",
+            low,
+            high,
+            salaries.len(),
+            salaries.iter().sum::<f64>() / salaries.len() as f64
+        );
+    }
+    if groups.len() < bins {
+        println!("(ties at the cut points left {} non-empty groups)", groups.len());
+    }
+    let salaries: Vec<Vec<f64>> = groups.into_iter().map(|(_, salaries)| salaries).collect();
+    let (f, p_value) = one_way_anova(&salaries);
+    println!("One-way ANOVA: F = {:.digits$}, p-value = {:.digits$}", f, p_value);
+}
+
+// Percentile of an already sorted slice using linear interpolation between
+// closest ranks (same as numpy's default). `p` is in [0, 1].
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let frac = rank - lower as f64;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * frac
+}
+
+// nth root of the product, computed through logarithms so large samples
+// don't overflow. NaN unless every value is positive.
+fn geometric_mean(data: &[f64]) -> f64 {
+    if data.is_empty() || data.iter().any(|&x| x.is_nan() || x <= 0.0) {
+        return f64::NAN;
+    }
+    (data.iter().map(|x| x.ln()).sum::<f64>() / data.len() as f64).exp()
+}
+
+// n divided by the sum of reciprocals. NaN unless every value is positive.
+fn harmonic_mean(data: &[f64]) -> f64 {
+    if data.is_empty() || data.iter().any(|&x| x.is_nan() || x <= 0.0) {
+        return f64::NAN;
+    }
+    data.len() as f64 / data.iter().map(|x| 1.0 / x).sum::<f64>()
+}
+
+// Most frequent value, compared exactly, so it suits discrete codes rather
+// than continuous data. Ties go to the smallest value; None for empty input.
+fn mode(data: &[f64]) -> Option<f64> {
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let mut best: Option<(f64, usize)> = None;
+    for run in sorted.chunk_by(|a, b| a == b) {
+        // Strictly greater keeps the earlier (smaller) value on ties.
+        if best.is_none_or(|(_, count)| run.len() > count) {
+            best = Some((run[0], run.len()));
+        }
+    }
+    best.map(|(value, _)| value)
+}
+
+// Percentage of values less than or equal to `value`: 0 below the minimum,
+// 100 at or above the maximum. NaN for empty data.
+fn percentile_rank(data: &[f64], value: f64) -> f64 {
+    if data.is_empty() {
+        return f64::NAN;
+    }
+    let at_or_below = data.iter().filter(|&&v| v <= value).count();
+    at_or_below as f64 / data.len() as f64 * 100.0
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "toml", derive(serde::Serialize, serde::Deserialize))]
+struct DescriptiveStats {
+    count: usize,
+    mean: f64,
+    min: f64,
+    q1: f64,
+    median: f64,
+    q3: f64,
+    max: f64,
+    variance: f64,
+    std_dev: f64,
+    skewness: f64,
+    kurtosis: f64,
+}
+
+// Central moments m2, m3, m4 about the mean, dividing by n.
+fn central_moments(data: &[f64]) -> (f64, f64, f64) {
+    let n = data.len() as f64;
+    let mean = data.iter().sum::<f64>() / n;
+    let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+    for v in data {
+        let d = v - mean;
+        m2 += d * d;
+        m3 += d * d * d;
+        m4 += d * d * d * d;
+    }
+    (m2 / n, m3 / n, m4 / n)
+}
+
+// Moment coefficient of skewness m3 / m2^1.5. NaN for fewer than three values
+// or constant data.
+fn skewness(data: &[f64]) -> f64 {
+    if data.len() < 3 {
+        return f64::NAN;
+    }
+    let (m2, m3, _) = central_moments(data);
+    if m2 == 0.0 {
+        return f64::NAN;
+    }
+    m3 / m2.powf(1.5)
+}
+
+// Excess kurtosis m4 / m2^2 - 3 (0 for a normal distribution). NaN for fewer
+// than four values or constant data.
+fn kurtosis(data: &[f64]) -> f64 {
+    if data.len() < 4 {
+        return f64::NAN;
+    }
+    let (m2, _, m4) = central_moments(data);
+    if m2 == 0.0 {
+        return f64::NAN;
+    }
+    m4 / (m2 * m2) - 3.0
+}
+
+// Pure computation behind print_stats. Fields are NaN when they are undefined
+// for the input (everything for an empty slice, variance/std dev for n = 1,
+// skewness below n = 3 and kurtosis below n = 4).
+fn compute_stats(data: &[f64]) -> DescriptiveStats {
+    let count = data.len();
+    if count == 0 {
+        return DescriptiveStats {
+            count,
+            mean: f64::NAN,
+            min: f64::NAN,
+            q1: f64::NAN,
+            median: f64::NAN,
+            q3: f64::NAN,
+            max: f64::NAN,
+            variance: f64::NAN,
+            std_dev: f64::NAN,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+        };
+    }
+
+    let mean = data.iter().sum::<f64>() / count as f64;
+    let min = data.iter().cloned().fold(f64::INFINITY, |a, b| a.min(b));
+    let max = data.iter().cloned().fold(f64::NEG_INFINITY, |a, b| a.max(b));
+
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    // Sample variance (n - 1), matching calculate_linear_regression.
+    let variance = if count > 1 {
+        data.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count as f64 - 1.0)
+    } else {
+        f64::NAN
+    };
+
+    DescriptiveStats {
+        count,
+        mean,
+        min,
+        q1: percentile(&sorted, 0.25),
+        median: percentile(&sorted, 0.5),
+        q3: percentile(&sorted, 0.75),
+        max,
+        variance,
+        std_dev: variance.sqrt(),
+        skewness: skewness(data),
+        kurtosis: kurtosis(data),
+    }
+}
+
+// Lower and upper fences Q1 - 1.5*IQR and Q3 + 1.5*IQR, or None for empty data.
+fn iqr_fences(data: &[f64]) -> Option<(f64, f64)> {
+    if data.is_empty() {
+        return None;
+    }
+    let stats = compute_stats(data);
+    let iqr = stats.q3 - stats.q1;
+    Some((stats.q1 - 1.5 * iqr, stats.q3 + 1.5 * iqr))
+}
+
+// Indices of values lying outside the IQR fences.
+fn outliers_iqr(data: &[f64]) -> Vec<usize> {
+    match iqr_fences(data) {
+        Some((lower, upper)) => (0..data.len())
+            .filter(|&i| data[i] < lower || data[i] > upper)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Standardizes to mean 0 and (sample) standard deviation 1. A zero-variance
+// column is returned unchanged with a warning.
+fn zscore_normalize(data: &[f64]) -> Vec<f64> {
+    let stats = compute_stats(data);
+    if stats.std_dev.is_nan() || stats.std_dev == 0.0 {
+        eprintln!("Warning: column has zero variance; leaving it unchanged");
+        return data.to_vec();
+    }
+    data.iter().map(|v| (v - stats.mean) / stats.std_dev).collect()
+}
+
+// Maps the minimum to 0 and the maximum to 1. A constant column has no range
+// to scale over, so it becomes all zeros with a warning.
+fn minmax_scale(data: &[f64]) -> Vec<f64> {
+    let stats = compute_stats(data);
+    let range = stats.max - stats.min;
+    if range == 0.0 {
+        eprintln!("Warning: column is constant; min-max scaling it to zeros");
+        return vec![0.0; data.len()];
+    }
+    data.iter().map(|v| (v - stats.min) / range).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scaling {
+    None,
+    ZScore,
+    MinMax,
+}
+
+impl Scaling {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "none" => Ok(Scaling::None),
+            "zscore" => Ok(Scaling::ZScore),
+            "minmax" => Ok(Scaling::MinMax),
+            _ => Err(format!(
+                "Invalid scaling '{}': expected zscore, minmax, or none",
+                value
+            )),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Scaling::None => "none",
+            Scaling::ZScore => "z-score",
+            Scaling::MinMax => "min-max",
+        }
+    }
+
+    fn apply(&self, data: &[f64]) -> Vec<f64> {
+        match self {
+            Scaling::None => data.to_vec(),
+            Scaling::ZScore => zscore_normalize(data),
+            Scaling::MinMax => minmax_scale(data),
+        }
+    }
+}
+
+// Rescales every predictor field (everything except salary) across the
+// given individuals so regression slopes are comparable between analyses.
+fn scale_features(individuals: &[Individual], scaling: Scaling) -> Vec<Individual> {
+    let mut scaled = individuals.to_vec();
+    for field in Field::ALL.into_iter().filter(|&field| field != Field::Salary) {
+        let column: Vec<f64> = scaled.iter().map(|ind| field.get(ind)).collect();
+        for (ind, value) in scaled.iter_mut().zip(scaling.apply(&column)) {
+            *field.get_mut(ind) = value;
+        }
+    }
+
+    scaled
+}
+
+// Values closer than this are considered equal by dedup_individuals.
+const DEDUP_EPSILON: f64 = 1e-9;
+
+// Removes individuals whose numeric fields are all within DEDUP_EPSILON of
+// an earlier one's, keeping the first occurrence (and its id). Rows with a
+// NaN or infinite field are never treated as duplicates. Kept rows are
+// bucketed by salary in steps of DEDUP_EPSILON; a row within epsilon of a
+// kept one lies in the same or an adjacent bucket, so those three are the
+// only ones compared field by field.
+fn dedup_individuals(individuals: Vec<Individual>) -> Vec<Individual> {
+    let mut buckets: HashMap<u64, Vec<Vec<f64>>> = HashMap::new();
+    individuals
+        .into_iter()
+        .filter(|ind| {
+            let values: Vec<f64> = Field::NUMERIC.iter().map(|field| field.get(ind)).collect();
+            if values.iter().any(|v| !v.is_finite()) {
+                return true;
+            }
+            // Kept as f64 bits so large salaries cannot saturate an integer
+            // key; adding 0.0 folds -0.0 into 0.0.
+            let step = (ind.salary / DEDUP_EPSILON).round();
+            let mut keys: Vec<u64> = [step - 1.0, step, step + 1.0].iter().map(|k| (k + 0.0).to_bits()).collect();
+            keys.dedup();
+            let duplicate = keys.iter().filter_map(|key| buckets.get(key)).flatten().any(|kept| {
+                kept.iter().zip(&values).all(|(a, b)| (a - b).abs() <= DEDUP_EPSILON)
+            });
+            if !duplicate {
+                buckets.entry((step + 0.0).to_bits()).or_default().push(values);
+            }
+            !duplicate
+        })
+        .collect()
+}
+
+// How many colliding ids the load-time check lists before eliding the rest.
+const MAX_REPORTED_DUPLICATE_IDS: usize = 10;
+
+// Ids carried by more than one individual, in ascending order. read_dataset
+// numbers rows continuously across files, so any hit means two sources were
+// combined without that numbering.
+fn duplicate_ids(individuals: &[Individual]) -> Vec<usize> {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for ind in individuals {
+        *counts.entry(ind.id).or_insert(0) += 1;
+    }
+    let mut duplicates: Vec<usize> = counts
+        .into_iter()
+        .filter(|&(_, count)| count > 1)
+        .map(|(id, _)| id)
+        .collect();
+    duplicates.sort_unstable();
+    duplicates
+}
+
+// Renumbers the individuals 0..n in their current order.
+fn reassign_ids(individuals: &mut [Individual]) {
+    for (id, ind) in individuals.iter_mut().enumerate() {
+        ind.id = id;
+    }
+}
+
+// Reads a two-column (id, value) CSV with a header row into a map. Rows that
+// don't parse are skipped with a count; a repeated id keeps its last value.
+fn read_join_column(path: &str, delimiter: u8) -> Result<HashMap<usize, f64>, AnalysisError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(open_input(path)?);
+
+    let mut values = HashMap::new();
+    let mut unparsable = 0;
+    for result in rdr.records() {
+        let record = result?;
+        let id = record.get(0).and_then(|raw| raw.trim().parse::<usize>().ok());
+        let value = record.get(1).and_then(|raw| parse_numeric(raw, false));
+        match (id, value) {
+            (Some(id), Some(value)) => {
+                values.insert(id, value);
+            }
+            _ => unparsable += 1,
+        }
+    }
+    if unparsable > 0 {
+        eprintln!("Warning: skipped {} unparsable rows in {}", unparsable, path);
+    }
+    Ok(values)
+}
+
+// Sets `external` from `values` by id and drops individuals without a match.
+// Returns the kept individuals and the number dropped.
+fn join_external(individuals: Vec<Individual>, values: &HashMap<usize, f64>) -> (Vec<Individual>, usize) {
+    let before = individuals.len();
+    let joined: Vec<Individual> = individuals
+        .into_iter()
+        .filter_map(|ind| {
+            values.get(&ind.id).map(|&external| Individual { external, ..ind })
+        })
+        .collect();
+    let dropped = before - joined.len();
+
+    (joined, dropped)
+}
+
+// Replaces each salary with its natural log. Non-positive salaries have no
+// log, so those individuals are dropped; returns the kept ones and the count.
+fn log_transform_salary(individuals: Vec<Individual>) -> (Vec<Individual>, usize) {
+    let before = individuals.len();
+    let logged: Vec<Individual> = individuals
+        .into_iter()
+        .filter(|ind| ind.salary > 0.0)
+        .map(|ind| Individual {
+            salary: ind.salary.ln(),
+            ..ind
+        })
+        .collect();
+    let dropped = before - logged.len();
+
+    (logged, dropped)
+}
+
+// Clamps values below the `lower_pct` percentile and above the `upper_pct`
+// percentile (both fractions in [0, 1]) to those percentiles. NaN values are
+// left alone and don't count towards the percentiles.
+fn winsorize(data: &[f64], lower_pct: f64, upper_pct: f64) -> Vec<f64> {
+    assert!(
+        (0.0..=1.0).contains(&lower_pct) && lower_pct <= upper_pct && upper_pct <= 1.0,
+        "Winsorizing percentiles must satisfy 0 <= lower <= upper <= 1"
+    );
+    let mut sorted: Vec<f64> = data.iter().copied().filter(|x| !x.is_nan()).collect();
+    if sorted.is_empty() {
+        return data.to_vec();
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let low = percentile(&sorted, lower_pct);
+    let high = percentile(&sorted, upper_pct);
+    data.iter().map(|x| x.clamp(low, high)).collect()
+}
+
+// Winsorizes salary at `fraction` on each end. Returns the individuals and
+// how many salaries were raised and lowered.
+fn winsorize_salaries(individuals: Vec<Individual>, fraction: f64) -> (Vec<Individual>, usize, usize) {
+    let salaries: Vec<f64> = individuals.iter().map(|ind| ind.salary).collect();
+    let capped = winsorize(&salaries, fraction, 1.0 - fraction);
+    let raised = salaries.iter().zip(&capped).filter(|(x, w)| x < w).count();
+    let lowered = salaries.iter().zip(&capped).filter(|(x, w)| x > w).count();
+    let individuals = individuals
+        .into_iter()
+        .zip(capped)
+        .map(|(ind, salary)| Individual { salary, ..ind })
+        .collect();
+    (individuals, raised, lowered)
+}
+
+// Drops individuals whose salary lies outside the IQR fences, keeping each
+// survivor's original id. Returns the kept individuals and the drop count.
+fn trim_salary_outliers(individuals: Vec<Individual>) -> (Vec<Individual>, usize) {
+    let salaries: Vec<f64> = individuals.iter().map(|ind| ind.salary).collect();
+    let Some((lower, upper)) = iqr_fences(&salaries) else {
+        return (individuals, 0);
+    };
+
+    let before = individuals.len();
+    let kept: Vec<Individual> = individuals
+        .into_iter()
+        .filter(|ind| ind.salary >= lower && ind.salary <= upper)
+        .collect();
+    let dropped = before - kept.len();
+
+    (kept, dropped)
+}
+
+fn print_stats(data: &[f64], precision: Option<usize>) {
+    let digits = precision.unwrap_or(2);
+    let shape_digits = precision.unwrap_or(4);
+    let stats = compute_stats(data);
+    if stats.count == 0 {
+        println!("no data");
+        return;
+    }
+
+    println!("Mean: {:.digits$}", stats.mean);
+    for (label, value) in [("Geometric Mean", geometric_mean(data)), ("Harmonic Mean", harmonic_mean(data))] {
+        if value.is_nan() {
+            println!("{}: undefined (needs all values positive)", label);
+        } else {
+            println!("{}: {:.digits$}", label, value);
+        }
+    }
+    if stats.count > 1 {
+        println!("Variance: {:.digits$}", stats.variance);
+        println!("Std Dev: {:.digits$}", stats.std_dev);
+    } else {
+        println!("Variance: undefined");
+        println!("Std Dev: undefined");
+    }
+    println!("Min: {:.digits$}", stats.min);
+    println!("Q1: {:.digits$}", stats.q1);
+    println!("Median: {:.digits$}", stats.median);
+    println!("Q3: {:.digits$}", stats.q3);
+    println!("Max: {:.digits$}", stats.max);
+    for (label, value) in [("Skewness", stats.skewness), ("Excess Kurtosis", stats.kurtosis)] {
+        if value.is_nan() {
+            println!("{}: undefined", label);
+        } else {
+            println!("{}: {:.shape_digits$}", label, value);
+        }
+    }
+}
+
+// A query typed at the --repl prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReplCommand {
+    Stats(Field),
+    Corr(Field, Field),
+    // Predict the target from this field at a value.
+    Predict(Field, f64),
+    Help,
+    Quit,
+}
+
+const REPL_USAGE: &str = "Commands: stats FIELD | corr FIELD FIELD | predict FIELD VALUE | help | quit";
+
+impl ReplCommand {
+    fn parse(line: &str) -> Result<ReplCommand, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["stats", field] => Ok(ReplCommand::Stats(parse_field(field, "field")?)),
+            ["corr", x, y] => Ok(ReplCommand::Corr(parse_field(x, "field")?, parse_field(y, "field")?)),
+            ["predict", field, value] => {
+                let value: f64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid value '{}': expected a number", value))?;
+                Ok(ReplCommand::Predict(parse_field(field, "field")?, value))
+            }
+            ["help"] => Ok(ReplCommand::Help),
+            ["quit"] | ["exit"] => Ok(ReplCommand::Quit),
+            _ => Err(format!("Unknown command '{}'", line.trim())),
+        }
+    }
+}
+
+// Answers commands from `input` against the loaded sample until `quit` or
+// end of input. Bad commands print the usage and the loop carries on.
+fn run_repl(sample: &Dataset, options: &AnalysisOptions, input: impl BufRead) -> Result<(), AnalysisError> {
+    println!("\nLoaded {} individuals. {}", sample.len(), REPL_USAGE);
+    print!("> ");
+    io::stdout().flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            match ReplCommand::parse(&line) {
+                Ok(ReplCommand::Quit) => return Ok(()),
+                Ok(command) => run_repl_command(sample, options, command),
+                Err(message) => println!("{}\n{}", message, REPL_USAGE),
+            }
+        }
+        print!("> ");
+        io::stdout().flush()?;
+    }
+    println!();
+    Ok(())
+}
+
+fn run_repl_command(sample: &Dataset, options: &AnalysisOptions, command: ReplCommand) {
+    let digits = options.precision.unwrap_or(4);
+    match command {
+        ReplCommand::Stats(field) => {
+            println!("{}:", field.label());
+            print_stats(&sample.column(field), options.precision);
+        }
+        ReplCommand::Corr(x, y) => {
+            let (xs, ys) = sample.finite_pair_columns(x, y);
+            println!(
+                "{} vs {}: Pearson = {:.digits$}, Spearman = {:.digits$}",
+                y.label(),
+                x.label(),
+                pearson_correlation(&xs, &ys),
+                spearman_correlation(&xs, &ys)
+            );
+        }
+        ReplCommand::Predict(field, x0) => {
+            let (xs, ys) = sample.finite_pair_columns(field, options.target);
+            let model = LinearModel::fit(&xs, &ys);
+            let line = FittedLine::new(&xs, model, std_error_of_estimate(&xs, &ys, model.slope, model.intercept));
+            let (low, high) = prediction_interval(&line, x0, options.confidence_level);
+            println!(
+                "{} at {} = {}: {:.digits$} ({}% PI: [{:.digits$}, {:.digits$}])",
+                options.target.label(),
+                field.label(),
+                x0,
+                model.predict(x0),
+                options.confidence_level * 100.0,
+                low,
+                high
+            );
+        }
+        ReplCommand::Help => println!("{}", REPL_USAGE),
+        ReplCommand::Quit => {}
+    }
+}
+
+#[derive(Debug)]
+struct Config {
+    command: Command,
+    // One or more dataset files, concatenated in order.
+    file_paths: Vec<String>,
+    sample_size: usize,
+    // Sample this share of the loaded individuals instead of sample_size.
+    sample_fraction: Option<f64>,
+    // Conditions every individual must meet before sampling; --filter may
+    // be repeated.
+    filters: Vec<Filter>,
+    seed: Option<u64>,
+    test_fraction: f64,
+    out_path: Option<String>,
+    residuals_path: Option<String>,
+    // Fitted-vs-actual dump for one analysis; the feature (also used by
+    // --jsonl) is resolved to the first selected analysis when --dump-feature
+    // isn't given.
+    predictions_path: Option<String>,
+    predictions_feature: Option<Field>,
+    report_path: Option<String>,
+    save_sample_path: Option<String>,
+    json: bool,
+    // One JSON object per sampled individual for the --dump-feature analysis.
+    jsonl: bool,
+    // The --json document as TOML; needs the `toml` feature.
+    toml: bool,
+    read_options: ReadOptions,
+    trim_outliers: bool,
+    // Cap salaries at this percentile on each end instead of dropping them.
+    winsorize: Option<f64>,
+    scaling: Scaling,
+    clusters: Option<usize>,
+    analysis: AnalysisOptions,
+    stream: bool,
+    poly_degree: Option<usize>,
+    // Individuals per window for the rolling correlation by age; None skips it.
+    rolling_window: Option<usize>,
+    // Individuals between window starts; defaults to half the window.
+    rolling_step: Option<usize>,
+    histogram_bins: usize,
+    folds: usize,
+    dedup: bool,
+    // Renumber individuals when loaded ids collide instead of failing.
+    reassign_ids: bool,
+    percentile_of: Option<f64>,
+    age_band_width: f64,
+    // Compare mean salary across quantile groups of this field.
+    anova_field: Option<Field>,
+    anova_bins: usize,
+    // Smooth the mean salary by age over this many ages; None skips it.
+    smooth_window: Option<usize>,
+    interaction: bool,
+    // Family influence enters the multiple regression as level indicators.
+    dummy_encode_influence: bool,
+    // Test whether this predictor improves a salary model on nested_base;
+    // an empty base means every other predictor.
+    nested_add: Option<Field>,
+    nested_base: Vec<Field>,
+    // Sample proportionally to this field instead of uniformly.
+    weight_by: Option<Field>,
+    // Keep the family influence proportions in the sample.
+    stratify: bool,
+    // Take the first sample_size individuals in this field's order, and
+    // split them systematically, instead of sampling at random.
+    sort_by: Option<Field>,
+    // Print this many parsed records and exit.
+    head: Option<usize>,
+    // Answer commands from stdin against the sample instead of running the
+    // analyses.
+    repl: bool,
+    // Validate the file and exit; holds the highest acceptable error rate.
+    check: Option<f64>,
+    // Also fit the multiple regression with this ridge penalty.
+    ridge_lambda: Option<f64>,
+    // (id, value) CSV joined onto the individuals as the external field.
+    join_path: Option<String>,
+}
+
+impl Config {
+    // --json, --jsonl and --toml keep stdout free of human-oriented output.
+    fn machine_output(&self) -> bool {
+        self.json || self.jsonl || self.toml
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            command: Command::All,
+            file_paths: vec!["career_dataset.csv".to_string()],
+            sample_size: 2_000,
+            sample_fraction: None,
+            filters: Vec::new(),
+            seed: None,
+            test_fraction: 0.2,
+            out_path: None,
+            residuals_path: None,
+            predictions_path: None,
+            predictions_feature: None,
+            report_path: None,
+            save_sample_path: None,
+            json: false,
+            jsonl: false,
+            toml: false,
+            read_options: ReadOptions::default(),
+            trim_outliers: false,
+            winsorize: None,
+            scaling: Scaling::None,
+            clusters: None,
+            analysis: AnalysisOptions::default(),
+            stream: false,
+            poly_degree: None,
+            rolling_window: None,
+            rolling_step: None,
+            histogram_bins: 10,
+            folds: 5,
+            dedup: false,
+            reassign_ids: false,
+            percentile_of: None,
+            age_band_width: 5.0,
+            smooth_window: None,
+            anova_field: None,
+            anova_bins: 4,
+            interaction: false,
+            dummy_encode_influence: false,
+            nested_add: None,
+            nested_base: Vec::new(),
+            weight_by: None,
+            sort_by: None,
+            stratify: false,
+            head: None,
+            repl: false,
+            check: None,
+            ridge_lambda: None,
+            join_path: None,
+        }
+    }
+}
+
+// Which part of the pipeline to run. `All` runs every section and is used
+// when no subcommand is given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    All,
+    Stats,
+    Correlate,
+    Regress,
+    Cluster,
+}
+
+impl Command {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stats" => Some(Command::Stats),
+            "correlate" => Some(Command::Correlate),
+            "regress" => Some(Command::Regress),
+            "cluster" => Some(Command::Cluster),
+            _ => None,
+        }
+    }
+
+    // Whether running this command includes the given section.
+    fn runs(&self, section: Command) -> bool {
+        *self == Command::All || *self == section
+    }
+}
+
+// Number of clusters for the `cluster` subcommand when --clusters is not given.
+const DEFAULT_CLUSTERS: usize = 3;
+
+// Seed for --sort-by runs without --seed.
+const SORTED_RUN_SEED: u64 = 0;
+
+// Share of rejected rows --check tolerates unless --max-error-rate is given.
+const DEFAULT_MAX_ERROR_RATE: f64 = 0.05;
+
+// Parses an inclusive "MIN,MAX" range.
+fn parse_range(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("Invalid range '{}': expected MIN,MAX", value);
+    let (low, high) = value.split_once(',').ok_or_else(invalid)?;
+    let low: f64 = low.trim().parse().map_err(|_| invalid())?;
+    let high: f64 = high.trim().parse().map_err(|_| invalid())?;
+    if low > high {
+        return Err(format!("Invalid range '{}': MIN is greater than MAX", value));
+    }
+    Ok((low, high))
+}
+
+// Accepts a delimiter name or a single literal ASCII character.
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "comma" => Ok(b','),
+        "tab" => Ok(b'\t'),
+        "semicolon" => Ok(b';'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!(
+            "Invalid delimiter '{}': expected comma, tab, semicolon, or a single character",
+            value
+        )),
+    }
+}
+
+fn flag_value<T: FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for {}", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: '{}'", flag, value))
+}
+
+// Parses the arguments after the program name. The first positional argument
+// is the dataset path; everything else is a `--flag value` option.
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut positional = Vec::new();
+    let mut sample_size_given = false;
+    let mut max_error_rate = None;
+    let mut iter = args.iter().peekable();
+
+    // An optional subcommand comes first, before the path and options.
+    if let Some(command) = iter.peek().and_then(|arg| Command::parse(arg)) {
+        config.command = command;
+        iter.next();
+    }
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sample-size" => {
+                config.sample_size = flag_value(arg, iter.next())?;
+                sample_size_given = true;
+            }
+            "--sample-fraction" => {
+                let fraction: f64 = flag_value(arg, iter.next())?;
+                if !(fraction > 0.0 && fraction <= 1.0) {
+                    return Err("--sample-fraction must be in (0, 1]".to_string());
+                }
+                config.sample_fraction = Some(fraction);
+            }
+            "--filter" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.filters.push(Filter::parse(&value)?);
+            }
+            "--seed" => config.seed = Some(flag_value(arg, iter.next())?),
+            "--out" => config.out_path = Some(flag_value(arg, iter.next())?),
+            "--residuals-out" => config.residuals_path = Some(flag_value(arg, iter.next())?),
+            "--dump-predictions" => config.predictions_path = Some(flag_value(arg, iter.next())?),
+            "--dump-feature" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.predictions_feature = Some(parse_field(&value, "dump feature")?);
+            }
+            "--report" => config.report_path = Some(flag_value(arg, iter.next())?),
+            "--save-sample" => config.save_sample_path = Some(flag_value(arg, iter.next())?),
+            "--json" => config.json = true,
+            "--jsonl" => config.jsonl = true,
+            "--toml" => config.toml = true,
+            "--stream" => config.stream = true,
+            "--trim-outliers" => config.trim_outliers = true,
+            "--winsorize" => {
+                let fraction: f64 = flag_value(arg, iter.next())?;
+                if !(fraction > 0.0 && fraction < 0.5) {
+                    return Err("--winsorize must be in (0, 0.5)".to_string());
+                }
+                config.winsorize = Some(fraction);
+            }
+            "--dedup" => config.dedup = true,
+            "--reassign-ids" => config.reassign_ids = true,
+            "--log-target" => config.analysis.log_target = true,
+            "--bootstrap" => config.analysis.bootstrap_resamples = flag_value(arg, iter.next())?,
+            "--percentile-of" => config.percentile_of = Some(flag_value(arg, iter.next())?),
+            "--confidence" => {
+                config.analysis.confidence_level = flag_value(arg, iter.next())?;
+                let level = config.analysis.confidence_level;
+                if !(level > 0.0 && level < 1.0) {
+                    return Err("--confidence must be between 0 and 1 (exclusive)".to_string());
+                }
+            }
+            "--weak-threshold" => config.analysis.strength_thresholds.0 = flag_value(arg, iter.next())?,
+            "--strong-threshold" => config.analysis.strength_thresholds.1 = flag_value(arg, iter.next())?,
+            "--auto-rank-ordinal" => config.analysis.auto_rank_ordinal = true,
+            "--precision" => config.analysis.precision = Some(flag_value(arg, iter.next())?),
+            "--predict-at" => {
+                let x0: f64 = flag_value(arg, iter.next())?;
+                if !x0.is_finite() {
+                    return Err("--predict-at must be a finite number".to_string());
+                }
+                config.analysis.predict_at = Some(x0);
+            }
+            "--huber-k" => {
+                config.analysis.huber_k = flag_value(arg, iter.next())?;
+                if config.analysis.huber_k.is_nan() || config.analysis.huber_k <= 0.0 {
+                    return Err("--huber-k must be positive".to_string());
+                }
+            }
+            "--features" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.analysis.features = parse_features(&value)?;
+            }
+            "--target" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.analysis.target = parse_field(&value, "target")?;
+            }
+            "--interaction" => config.interaction = true,
+            "--dummy-encode-influence" => config.dummy_encode_influence = true,
+            "--nested-add" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.nested_add = Some(parse_field(&value, "nested model field")?);
+            }
+            "--nested-base" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.nested_base = parse_features(&value)?;
+            }
+            "--ridge-lambda" => {
+                let lambda: f64 = flag_value(arg, iter.next())?;
+                if lambda.is_nan() || lambda <= 0.0 {
+                    return Err("--ridge-lambda must be positive".to_string());
+                }
+                config.ridge_lambda = Some(lambda);
+            }
+            "--poly-degree" => config.poly_degree = Some(flag_value(arg, iter.next())?),
+            "--rolling-window" => {
+                let window: usize = flag_value(arg, iter.next())?;
+                if window < 3 {
+                    return Err("--rolling-window must be at least 3".to_string());
+                }
+                config.rolling_window = Some(window);
+            }
+            "--rolling-step" => {
+                let step: usize = flag_value(arg, iter.next())?;
+                if step == 0 {
+                    return Err("--rolling-step must be at least 1".to_string());
+                }
+                config.rolling_step = Some(step);
+            }
+            "--folds" => {
+                config.folds = flag_value(arg, iter.next())?;
+                if config.folds < 2 {
+                    return Err("--folds must be at least 2".to_string());
+                }
+            }
+            "--stratify" => config.stratify = true,
+            "--join" => {
+                config.join_path = Some(flag_value(arg, iter.next())?);
+                config.analysis.joined = true;
+            }
+            "--head" => config.head = Some(flag_value(arg, iter.next())?),
+            "--repl" => config.repl = true,
+            "--check" => {
+                config.check.get_or_insert(DEFAULT_MAX_ERROR_RATE);
+            }
+            "--max-error-rate" => {
+                let rate: f64 = flag_value(arg, iter.next())?;
+                if !(0.0..=1.0).contains(&rate) {
+                    return Err("--max-error-rate must be between 0 and 1".to_string());
+                }
+                max_error_rate = Some(rate);
+            }
+            "--sort-by" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.sort_by = Some(parse_field(&value, "sort field")?);
+            }
+            "--weight-by" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.weight_by = Some(parse_field(&value, "weight field")?);
+            }
+            "--age-band" => {
+                config.age_band_width = flag_value(arg, iter.next())?;
+                if config.age_band_width.is_nan() || config.age_band_width <= 0.0 {
+                    return Err("--age-band must be positive".to_string());
+                }
+            }
+            "--anova" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.anova_field = Some(parse_field(&value, "ANOVA field")?);
+            }
+            "--anova-bins" => {
+                config.anova_bins = flag_value(arg, iter.next())?;
+                if config.anova_bins < 2 {
+                    return Err("--anova-bins must be at least 2".to_string());
+                }
+            }
+            "--smooth-window" => {
+                let window: usize = flag_value(arg, iter.next())?;
+                if window == 0 {
+                    return Err("--smooth-window must be at least 1".to_string());
+                }
+                config.smooth_window = Some(window);
+            }
+            "--bins" => {
+                config.histogram_bins = flag_value(arg, iter.next())?;
+                if config.histogram_bins == 0 {
+                    return Err("--bins must be at least 1".to_string());
+                }
+            }
+            "--clusters" => config.clusters = Some(flag_value(arg, iter.next())?),
+            "--scale" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.scaling = Scaling::parse(&value)?;
+            }
+            "--impute" => config.read_options.impute = true,
+            "--verbose" => config.read_options.verbose = true,
+            "--thousands-separators" => config.read_options.thousands_separators = true,
+            "--lenient-columns" => config.read_options.lenient_columns = true,
+            "--limit-columns" => config.read_options.fixed_column_positions = true,
+            "--satisfaction-range" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.read_options.satisfaction_range = parse_range(&value)?;
+            }
+            "--max-records" => config.read_options.max_records = flag_value(arg, iter.next())?,
+            "--delimiter" => {
+                let value: String = flag_value(arg, iter.next())?;
+                config.read_options.delimiter = parse_delimiter(&value)?;
+            }
+            "--test-fraction" => {
+                config.test_fraction = flag_value(arg, iter.next())?;
+                if !(config.test_fraction > 0.0 && config.test_fraction < 1.0) {
+                    return Err("--test-fraction must be between 0 and 1 (exclusive)".to_string());
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if config.join_path.is_none() {
+        let uses_external = config.analysis.target == Field::External
+            || config.analysis.features.contains(&Field::External)
+            || config.weight_by == Some(Field::External)
+            || config.predictions_feature == Some(Field::External)
+            || config.sort_by == Some(Field::External)
+            || config.nested_add == Some(Field::External)
+            || config.nested_base.contains(&Field::External)
+            || config.filters.iter().any(|filter| filter.field == Field::External);
+        if uses_external {
+            return Err(format!("'{}' needs a column loaded with --join", Field::External.name()));
+        }
+    }
+    if config.stream && config.join_path.is_some() {
+        return Err("--join cannot be combined with --stream".to_string());
+    }
+    if config.read_options.lenient_columns && config.read_options.fixed_column_positions {
+        return Err("--lenient-columns cannot be combined with --limit-columns".to_string());
+    }
+    if let Some(rate) = max_error_rate {
+        match config.check.as_mut() {
+            Some(check) => *check = rate,
+            None => return Err("--max-error-rate needs --check".to_string()),
+        }
+    }
+    if sample_size_given && config.sample_fraction.is_some() {
+        return Err("--sample-size and --sample-fraction cannot be combined".to_string());
+    }
+    if config.stream && config.sample_fraction.is_some() {
+        return Err("--sample-fraction cannot be combined with --stream".to_string());
+    }
+    if config.rolling_step.is_some() && config.rolling_window.is_none() {
+        return Err("--rolling-step needs --rolling-window".to_string());
+    }
+    if config.stream && config.analysis.predict_at.is_some() {
+        return Err("--predict-at cannot be combined with --stream".to_string());
+    }
+    if config.stream && !config.filters.is_empty() {
+        return Err("--filter cannot be combined with --stream".to_string());
+    }
+    if config.json && config.jsonl {
+        return Err("--json and --jsonl cannot be combined".to_string());
+    }
+    if config.toml && (config.json || config.jsonl) {
+        return Err("--toml cannot be combined with --json or --jsonl".to_string());
+    }
+    #[cfg(not(feature = "toml"))]
+    if config.toml {
+        return Err("--toml needs a build with `--features toml`".to_string());
+    }
+    if config.stream
+        && (config.machine_output() || config.read_options.impute || config.dedup || config.analysis.log_target)
+    {
+        return Err("--stream cannot be combined with --json, --jsonl, --toml, --impute, --dedup or --log-target".to_string());
+    }
+    if config.stream && (config.weight_by.is_some() || config.stratify) {
+        return Err("--weight-by and --stratify cannot be combined with --stream".to_string());
+    }
+    // The streaming path only prints the sample check and the streamed
+    // regressions, so these would otherwise be silently ignored.
+    if config.stream
+        && (config.save_sample_path.is_some()
+            || config.out_path.is_some()
+            || config.report_path.is_some()
+            || config.percentile_of.is_some()
+            || config.trim_outliers
+            || config.clusters.is_some()
+            || config.scaling != Scaling::None)
+    {
+        return Err(
+            "--stream cannot be combined with --save-sample, --out, --report, --percentile-of, --trim-outliers, --clusters or --scale"
+                .to_string(),
+        );
+    }
+    if config.head.is_some() && (config.stream || config.machine_output()) {
+        return Err("--head cannot be combined with --stream, --json or --jsonl".to_string());
+    }
+    if config.check.is_some() && (config.stream || config.machine_output() || config.head.is_some()) {
+        return Err("--check cannot be combined with --stream, --json, --jsonl or --head".to_string());
+    }
+    if config.stratify && config.weight_by.is_some() {
+        return Err("--stratify cannot be combined with --weight-by".to_string());
+    }
+    if let Some(added) = config.nested_add {
+        if config.nested_base.is_empty() {
+            config.nested_base = Field::ALL
+                .into_iter()
+                .filter(|&field| field != Field::Salary && field != added)
+                .filter(|&field| config.analysis.joined || field != Field::External)
+                .collect();
+        }
+        if added == Field::Salary || config.nested_base.contains(&Field::Salary) {
+            return Err("Salary is the response of the nested models, not a predictor".to_string());
+        }
+        if config.nested_base.contains(&added) {
+            return Err(format!("--nested-add '{}' is already in the base model", added.name()));
+        }
+    } else if !config.nested_base.is_empty() {
+        return Err("--nested-base needs --nested-add".to_string());
+    }
+    if config.repl && (config.stream || config.machine_output() || config.head.is_some() || config.check.is_some()) {
+        return Err("--repl cannot be combined with --stream, --json, --jsonl, --toml, --head or --check".to_string());
+    }
+    if config.winsorize.is_some() && (config.trim_outliers || config.stream) {
+        return Err("--winsorize cannot be combined with --trim-outliers or --stream".to_string());
+    }
+    if config.sort_by.is_some() && (config.stream || config.stratify || config.weight_by.is_some()) {
+        return Err("--sort-by cannot be combined with --stream, --stratify or --weight-by".to_string());
+    }
+    // A sorted head is meant to give the same output on every run, so the
+    // sections that still draw random numbers (bootstrap, folds, clustering)
+    // get a fixed seed unless one is given.
+    if config.sort_by.is_some() {
+        config.seed.get_or_insert(SORTED_RUN_SEED);
+    }
+    if config.stream && config.command != Command::All {
+        return Err("--stream cannot be combined with a subcommand".to_string());
+    }
+    if config.machine_output() && !config.command.runs(Command::Correlate) {
+        return Err("--json and --jsonl are only supported for the correlation analyses".to_string());
+    }
+    if config.machine_output() && config.percentile_of.is_some() {
+        return Err("--percentile-of cannot be combined with --json or --jsonl".to_string());
+    }
+    let (weak, strong) = config.analysis.strength_thresholds;
+    if !(weak > 0.0 && weak < strong && strong <= 1.0) {
+        return Err("--weak-threshold and --strong-threshold need 0 < weak < strong <= 1".to_string());
+    }
+    if config.analysis.log_target && config.analysis.target != Field::Salary {
+        return Err("--log-target only applies to the salary target".to_string());
+    }
+    if selected_analyses(&config.analysis).is_empty() {
+        return Err("--features leaves no predictor besides the --target".to_string());
+    }
+    if config.predictions_path.is_some() || config.jsonl {
+        if !config.command.runs(Command::Correlate) {
+            return Err("--dump-predictions needs the correlation analyses; use it with `correlate` or no subcommand".to_string());
+        }
+        let selected = selected_analyses(&config.analysis);
+        let feature = *config.predictions_feature.get_or_insert(selected[0]);
+        if !selected.contains(&feature) {
+            return Err(format!("--dump-feature '{}' is not one of the selected analyses", feature.name()));
+        }
+    } else if config.predictions_feature.is_some() {
+        return Err("--dump-feature needs --dump-predictions or --jsonl".to_string());
+    }
+    if config.report_path.is_some() && !config.command.runs(Command::Correlate) {
+        return Err("--report needs the correlation analyses; use it with `correlate` or no subcommand".to_string());
+    }
+
+    if !positional.is_empty() {
+        config.file_paths = positional;
+    }
+    if config.repl && config.file_paths.iter().any(|path| path == STDIN_PATH) {
+        return Err("--repl reads commands from standard input, so the dataset can't come from it".to_string());
+    }
+    if config.stream && config.file_paths.len() > 1 {
+        return Err("--stream reads a single file".to_string());
+    }
+    let stdin_reads = config
+        .file_paths
+        .iter()
+        .chain(&config.join_path)
+        .filter(|path| *path == STDIN_PATH)
+        .count();
+    if stdin_reads > 1 {
+        return Err(format!("'{}' (standard input) can only be read once", STDIN_PATH));
+    }
+
+    Ok(config)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        // The empty-dataset message reads the same as before it became an
+        // error; only the exit status changed.
+        match err {
+            AnalysisError::EmptyDataset => eprintln!("{}", err),
+            _ => eprintln!("Error: {}", err),
+        }
+        process::exit(err.exit_code());
+    }
+}
+
+fn run(args: &[String]) -> Result<(), AnalysisError> {
+    let mut config = match parse_args(args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("Usage: finalproject [stats|correlate|regress|cluster] [PATH...] [--sample-size N] [--sample-fraction F] [--filter EXPR] [--seed N] [--test-fraction F] [--out FILE] [--residuals-out FILE] [--dump-predictions FILE] [--dump-feature NAME] [--report FILE] [--save-sample FILE] [--json] [--jsonl] [--toml] [--impute] [--verbose] [--thousands-separators] [--lenient-columns] [--limit-columns] [--satisfaction-range MIN,MAX] [--delimiter D] [--max-records N] [--trim-outliers] [--winsorize F] [--dedup] [--reassign-ids] [--log-target] [--bootstrap N] [--percentile-of SALARY] [--scale zscore|minmax|none] [--clusters K] [--confidence LEVEL] [--predict-at X] [--precision N] [--huber-k K] [--auto-rank-ordinal] [--weak-threshold R] [--strong-threshold R] [--features NAME,...] [--target NAME] [--stream] [--poly-degree D] [--rolling-window N] [--rolling-step N] [--interaction] [--dummy-encode-influence] [--nested-add FIELD] [--nested-base NAME,...] [--ridge-lambda L] [--bins N] [--age-band YEARS] [--smooth-window N] [--anova FIELD] [--anova-bins N] [--folds K] [--weight-by FIELD] [--sort-by FIELD] [--stratify] [--head N] [--repl] [--join FILE] [--check] [--max-error-rate F]");
+            return Err(AnalysisError::InvalidArgument(message));
+        }
+    };
+
+    for file_path in &config.file_paths {
+        if file_path != STDIN_PATH && !Path::new(file_path).exists() {
+            return Err(AnalysisError::InvalidArgument(format!(
+                "dataset file '{}' does not exist",
+                file_path
+            )));
+        }
+    }
+
+    if config.stream {
+        let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+        println!("Random seed: {}", seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        // Streaming exists for files too large to load, so no record cap applies.
+        let options = ReadOptions {
+            max_records: usize::MAX,
+            ..config.read_options.clone()
+        };
+        return perform_streaming_analysis(
+            &config.file_paths[0],
+            &options,
+            config.sample_size,
+            config.histogram_bins,
+            &config.analysis,
+            &mut rng,
+        );
+    }
+
+    let file_paths: Vec<&str> = config.file_paths.iter().map(String::as_str).collect();
+    let (mut individuals, report) = read_dataset(&file_paths, &config.read_options)?;
+
+    let duplicates = duplicate_ids(&individuals);
+    if !duplicates.is_empty() {
+        let shown: Vec<String> = duplicates
+            .iter()
+            .take(MAX_REPORTED_DUPLICATE_IDS)
+            .map(ToString::to_string)
+            .collect();
+        eprintln!(
+            "Found {} duplicate ids: {}{}",
+            duplicates.len(),
+            shown.join(", "),
+            if duplicates.len() > MAX_REPORTED_DUPLICATE_IDS { ", ..." } else { "" }
+        );
+        if !config.reassign_ids {
+            return Err(AnalysisError::ParseError(
+                "individual ids are not unique (rerun with --reassign-ids to renumber them)".to_string(),
+            ));
+        }
+        reassign_ids(&mut individuals);
+        eprintln!("Reassigned ids 0..{}", individuals.len());
+    }
+
+    if let Some(max_error_rate) = config.check {
+        return check_dataset(individuals.len(), &report, max_error_rate);
+    }
+
+    let mut counts = PipelineCounts::default();
+
+    if config.dedup {
+        let loaded = individuals.len();
+        individuals = dedup_individuals(individuals);
+        counts.duplicates = loaded - individuals.len();
+        eprintln!("Removed {} duplicate records", counts.duplicates);
+    }
+
+    if let Some(join_path) = &config.join_path {
+        let values = read_join_column(join_path, config.read_options.delimiter)?;
+        let (joined, dropped) = join_external(individuals, &values);
+        individuals = joined;
+        counts.unjoined = dropped;
+        eprintln!(
+            "Joined {} values from {}; dropped {} individuals without a matching id",
+            values.len(),
+            join_path,
+            dropped
+        );
+    }
+
+    if !config.filters.is_empty() {
+        let loaded = individuals.len();
+        individuals = apply_filters(individuals, &config.filters);
+        counts.filtered = loaded - individuals.len();
+        let conditions: Vec<String> = config.filters.iter().map(Filter::to_string).collect();
+        eprintln!(
+            "Filter {} kept {} of {} individuals",
+            conditions.join(" and "),
+            individuals.len(),
+            loaded
+        );
+    }
+
+    if individuals.is_empty() {
+        return Err(AnalysisError::EmptyDataset);
+    }
+
+    if let Some(n) = config.head {
+        for line in head_table(&individuals, n) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    // Without --seed a fresh seed is drawn from thread_rng, so runs still differ
+    // by default but any run can be reproduced from the printed seed.
+    let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+    if !config.machine_output() {
+        println!("Random seed: {}", seed);
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    config.analysis.bootstrap_seed = seed;
+
+    if let Some(fraction) = config.sample_fraction {
+        config.sample_size = fraction_sample_size(fraction, individuals.len());
+        if !config.machine_output() {
+            println!(
+                "Sampling {:.1}% of {} loaded individuals: {} records",
+                fraction * 100.0,
+                individuals.len(),
+                config.sample_size
+            );
+        }
+    }
+    if config.sample_size > individuals.len() {
+        eprintln!(
+            "Requested sample size {} exceeds the {} loaded individuals; using the full dataset",
+            config.sample_size,
+            individuals.len()
+        );
+    }
+    let final_sample = Dataset(match (config.sort_by, config.weight_by) {
+        (Some(field), _) => {
+            if !config.machine_output() {
+                println!(
+                    "Deterministic sample: the first {} individuals by {} (not a random sample)",
+                    config.sample_size.min(individuals.len()),
+                    field.name()
+                );
+            }
+            sorted_head(&individuals, field, config.sample_size)
+        }
+        (None, Some(field)) => weighted_sample(&individuals, config.sample_size, |ind| field.get(ind), &mut rng),
+        (None, None) if config.stratify => {
+            let sample = stratified_sample(&individuals, config.sample_size, &mut rng);
+            if !config.machine_output() {
+                print_stratification(&individuals, &sample);
+            }
+            sample
+        }
+        (None, None) => reservoir_sample(individuals.into_iter(), config.sample_size, &mut rng),
+    });
+
+    counts.sampled = final_sample.len();
+
+    if let Some(save_sample_path) = &config.save_sample_path {
+        write_sample_csv(save_sample_path, &final_sample, config.read_options.delimiter)?;
+        if !config.machine_output() {
+            println!("Wrote {} sampled records to {}", final_sample.len(), save_sample_path);
+        }
+    }
+
+    if config.repl {
+        return run_repl(&final_sample, &config.analysis, io::stdin().lock());
+    }
+
+    // Outlier trimming and winsorizing only affect the regressions; the
+    // sample verification below still describes the untouched sample.
+    let analysis_sample = if config.trim_outliers {
+        let (kept, dropped) = trim_salary_outliers(final_sample.to_vec());
+        counts.trimmed = dropped;
+        if !config.machine_output() {
+            println!("Trimmed {} salary outliers before regression ({} remain)", dropped, kept.len());
+        }
+        kept
+    } else if let Some(fraction) = config.winsorize {
+        let (capped, raised, lowered) = winsorize_salaries(final_sample.to_vec(), fraction);
+        if !config.machine_output() {
+            println!(
+                "Winsorized salaries at {}% on each end before regression: {} raised, {} lowered",
+                fraction * 100.0,
+                raised,
+                lowered
+            );
+        }
+        capped
+    } else {
+        final_sample.to_vec()
+    };
+
+    let analysis_sample = if config.analysis.log_target {
+        let (logged, dropped) = log_transform_salary(analysis_sample);
+        counts.log_dropped = dropped;
+        if dropped > 0 {
+            eprintln!("Warning: skipped {} non-positive salaries for --log-target", dropped);
+        }
+        logged
+    } else {
+        analysis_sample
+    };
+
+    let analysis_sample = scale_features(&analysis_sample, config.scaling);
+
+    let (train, test) = match config.sort_by {
+        Some(_) => systematic_split(&analysis_sample, config.test_fraction),
+        None => train_test_split(&analysis_sample, config.test_fraction, &mut rng),
+    };
+    let (train, test) = (Dataset(train), Dataset(test));
+
+    // JSON modes keep stdout machine-readable: no human-oriented sections.
+    if config.machine_output() {
+        let results = compute_correlations(&train, &test, &config.analysis);
+        if let Some(out_path) = &config.out_path {
+            write_results_csv(out_path, &results, config.read_options.delimiter)?;
+        }
+        if let Some(residuals_path) = &config.residuals_path {
+            write_residuals_csv(
+                residuals_path,
+                &train,
+                &results,
+                &config.analysis,
+                config.read_options.delimiter,
+            )?;
+        }
+        if let (Some(path), Some(feature)) = (&config.predictions_path, config.predictions_feature) {
+            write_predictions_csv(path, &train, &results, &config.analysis, feature, config.read_options.delimiter)?;
+        }
+        if let Some(report_path) = &config.report_path {
+            std::fs::write(report_path, markdown_report(seed, &final_sample, &results))?;
+        }
+        if config.jsonl {
+            let feature = config.predictions_feature.expect("--jsonl resolves the feature when parsing");
+            for line in predictions_jsonl(&analysis_sample, &results, &config.analysis, feature) {
+                println!("{}", line);
+            }
+        } else if config.toml {
+            #[cfg(feature = "toml")]
+            print!("{}", results_to_toml(seed, &final_sample, &results));
+        } else {
+            println!("{}", results_to_json(seed, &final_sample, &results));
+        }
+        return Ok(());
+    }
+
+    let command = config.command;
+
+    if let Some(salary) = config.percentile_of {
+        let salaries: Vec<f64> = final_sample.iter().map(|ind| ind.salary).collect();
+        println!(
+            "\nA salary of {:.2} is at the {:.1}th percentile of the sample",
+            salary,
+            percentile_rank(&salaries, salary)
+        );
+    }
+
+    if command.runs(Command::Stats) {
+        print_sample_verification(&final_sample, config.histogram_bins, config.analysis.precision);
+
+        print_salary_by_family_influence(&final_sample, config.analysis.precision);
+
+        print_age_bands(&final_sample, config.age_band_width, config.analysis.precision);
+
+        if let Some(window) = config.smooth_window {
+            print_salary_trend(&final_sample, window, config.analysis.precision);
+        }
+    }
+
+    if command.runs(Command::Correlate) {
+        let results = perform_correlation_analysis(&train, &test, config.scaling, &config.analysis)?;
+
+        if let Some(out_path) = &config.out_path {
+            write_results_csv(out_path, &results, config.read_options.delimiter)?;
+            println!("\nWrote correlation results to {}", out_path);
+        }
+
+        if let Some(residuals_path) = &config.residuals_path {
+            write_residuals_csv(
+                residuals_path,
+                &train,
+                &results,
+                &config.analysis,
+                config.read_options.delimiter,
+            )?;
+            println!("Wrote residuals to {}", residuals_path);
+        }
+
+        if let (Some(path), Some(feature)) = (&config.predictions_path, config.predictions_feature) {
+            write_predictions_csv(path, &train, &results, &config.analysis, feature, config.read_options.delimiter)?;
+            println!("Wrote fitted-vs-actual values to {}", path);
+        }
+
+        if let Some(report_path) = &config.report_path {
+            std::fs::write(report_path, markdown_report(seed, &final_sample, &results))?;
+            println!("Wrote Markdown report to {}", report_path);
+        }
+
+        perform_cross_validation(&analysis_sample, config.folds, &config.analysis, &mut rng);
+
+        print_correlation_matrix(&final_sample, config.analysis.precision);
+
+        if let Some(window) = config.rolling_window {
+            let step = config.rolling_step.unwrap_or((window / 2).max(1));
+            print_rolling_correlation(&final_sample, window, step, config.analysis.precision);
+        }
+
+        if let Some(field) = config.anova_field {
+            print_anova_by_bins(&final_sample, field, config.anova_bins, config.analysis.precision);
+        }
+    }
+
+    if command.runs(Command::Regress) {
+        perform_multiple_regression(
+            &final_sample,
+            config.interaction,
+            config.ridge_lambda,
+            config.dummy_encode_influence,
+            config.analysis.precision,
+        );
+
+        if let Some(added) = config.nested_add {
+            print_nested_model_comparison(&final_sample, &config.nested_base, added, config.analysis.precision);
+        }
+
+        if let Some(degree) = config.poly_degree {
+            perform_polynomial_regression(&final_sample, degree, config.analysis.precision);
+        }
+
+        perform_logistic_regression(&final_sample, config.analysis.precision);
+    }
+
+    // The full pipeline only clusters when asked to; the subcommand always does.
+    let clusters = match command {
+        Command::Cluster => Some(config.clusters.unwrap_or(DEFAULT_CLUSTERS)),
+        _ => config.clusters,
+    };
+    if let (true, Some(k)) = (command.runs(Command::Cluster), clusters) {
+        perform_clustering(&final_sample, k, config.analysis.precision, &mut rng);
+    }
+
+    print_pipeline_accounting(&report, &counts);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // Test linear regression with a simple known dataset
+    #[test]
+    fn test_calculate_linear_regression() {
+        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = vec![2.0, 4.0, 6.0, 8.0, 10.0];
+        
+        let (slope, intercept, correlation, r_squared) = 
+            calculate_linear_regression(&x, &y);
+
+        assert!((slope - 2.0).abs() < 1e-6, "Slope should be 2");
+        assert!((intercept - 0.0).abs() < 1e-6, "Intercept should be 0");
+        assert!((correlation - 1.0).abs() < 1e-6, "Correlation should be 1");
+        assert!((r_squared - 1.0).abs() < 1e-6, "R-squared should be 1");
+    }
+
+    // Test covariance of a variable with itself is its sample variance
+    #[test]
+    fn test_covariance() {
+        let x = [2.0, 4.0, 4.0, 5.0, 7.0, 9.0];
+        let y = [1.0, 3.0, 2.0, 5.0, 4.0, 6.0];
+
+        assert!((covariance(&x, &x) - compute_stats(&x).variance).abs() < 1e-12);
+        // Sum of cross-deviations is 20.5 over n - 1 = 5.
+        assert!((covariance(&x, &y) - 4.1).abs() < 1e-12);
+        assert!((covariance(&x, &y) - covariance(&y, &x)).abs() < 1e-12);
+        assert!(covariance(&[1.0], &[2.0]).is_nan());
+    }
+
+    // Test predictions from a model fitted on the perfect line y = 2x
+    #[test]
+    fn test_linear_model_predict() {
+        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = vec![2.0, 4.0, 6.0, 8.0, 10.0];
+
+        let model = LinearModel::fit(&x, &y);
+
+        assert!((model.predict(7.0) - 14.0).abs() < 1e-9);
+        let predictions = model.predict_many(&[0.0, 2.5, -1.0]);
+        assert_eq!(predictions.len(), 3);
+        assert!((predictions[0] - 0.0).abs() < 1e-9);
+        assert!((predictions[1] - 5.0).abs() < 1e-9);
+        assert!((predictions[2] + 2.0).abs() < 1e-9);
+    }
+
+    // Test non-finite pairs are dropped pairwise instead of poisoning the fit
+    #[test]
+    fn test_linear_regression_skips_non_finite() {
+        let x = vec![1.0, 2.0, f64::NAN, 3.0, 4.0, 5.0];
+        let y = vec![2.0, 4.0, 100.0, 6.0, f64::INFINITY, 10.0];
+
+        let (slope, intercept, correlation, _) = calculate_linear_regression(&x, &y);
+
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!(intercept.abs() < 1e-9);
+        assert!((correlation - 1.0).abs() < 1e-9);
+    }
+
+    // Test every statistic in compute_correlations ignores non-finite pairs,
+    // matching an analysis of the sample with those rows removed
+    #[test]
+    fn test_compute_correlations_skips_non_finite() {
+        let mut sample = sample_individuals(12);
+        for ind in sample.iter_mut() {
+            ind.salary += ((ind.id * 37) % 11) as f64 * 500.0;
+        }
+        let clean: Vec<Individual> = sample.iter().filter(|ind| ind.id != 3 && ind.id != 6).cloned().collect();
+        sample[3].salary = f64::NAN;
+        sample[6].salary = f64::INFINITY;
+
+        let options = AnalysisOptions::default();
+        let results = compute_correlations(&Dataset(sample), &Dataset::default(), &options);
+        let expected = compute_correlations(&Dataset(clean), &Dataset::default(), &options);
+
+        let same = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-9;
+        for (result, expected) in results.iter().zip(&expected) {
+            assert!(same(result.spearman, expected.spearman), "{}", result.title);
+            assert!(same(result.rmse, expected.rmse) && same(result.mae, expected.mae));
+            assert!(same(result.p_value, expected.p_value) && same(result.slope_std_error, expected.slope_std_error));
+            assert!(same(result.theil_sen.0, expected.theil_sen.0) && same(result.covariance, expected.covariance));
+        }
+        let experience = &results[1];
+        assert!(experience.spearman.is_finite() && experience.rmse.is_finite() && experience.p_value.is_finite());
+    }
+
+    // Test the single-pass sums on a noisy fixture and on large offsets
+    #[test]
+    fn test_calculate_linear_regression_single_pass() {
+        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = vec![2.0, 4.0, 5.0, 4.0, 5.0];
+
+        let (slope, intercept, correlation, r_squared) = 
+            calculate_linear_regression(&x, &y);
+
+        assert!((slope - 0.6).abs() < 1e-9, "Slope should be 0.6");
+        assert!((intercept - 2.2).abs() < 1e-9, "Intercept should be 2.2");
+        assert!((correlation - 0.6_f64.sqrt()).abs() < 1e-9);
+        assert!((r_squared - 0.6).abs() < 1e-9, "R-squared should be 0.6");
+
+        // Shifting both variables by a large constant only moves the intercept.
+        let big_x: Vec<f64> = x.iter().map(|v| v + 1e9).collect();
+        let big_y: Vec<f64> = y.iter().map(|v| v + 1e9).collect();
+        let (big_slope, _, big_correlation, _) = calculate_linear_regression(&big_x, &big_y);
+        assert!((big_slope - 0.6).abs() < 1e-9);
+        assert!((big_correlation - correlation).abs() < 1e-9);
+    }
+
+    // Test degenerate regression inputs are reported as NaN instead of inf
+    #[test]
+    fn test_linear_regression_degenerate_inputs() {
+        let (slope, intercept, correlation, _) =
+            calculate_linear_regression(&[3.0, 3.0, 3.0], &[1.0, 2.0, 3.0]);
+        assert!(slope.is_nan() && intercept.is_nan() && correlation.is_nan());
+
+        let (slope, _, correlation, _) = calculate_linear_regression(&[1.0], &[2.0]);
+        assert!(slope.is_nan() && correlation.is_nan());
+
+        // A constant y still has a well-defined flat fit.
+        let (slope, intercept, correlation, _) =
+            calculate_linear_regression(&[1.0, 2.0, 3.0], &[4.0, 4.0, 4.0]);
+        assert_eq!(slope, 0.0);
+        assert_eq!(intercept, 4.0);
+        assert!(correlation.is_nan());
+        assert_eq!(correlation_strength(correlation, (0.3, 0.7)), "Undefined");
+    }
+
+    // Test the strength labels follow the configured cutoffs
+    #[test]
+    fn test_correlation_strength_thresholds() {
+        assert_eq!(correlation_strength(-0.25, (0.3, 0.7)), "Weak");
+        assert_eq!(correlation_strength(0.5, (0.3, 0.7)), "Moderate");
+        assert_eq!(correlation_strength(0.5, (0.1, 0.4)), "Strong");
+        assert_eq!(correlation_strength(0.1, (0.1, 0.4)), "Moderate");
+
+        let config = parse_args(&args(&["--weak-threshold", "0.2", "--strong-threshold", "0.5"])).unwrap();
+        assert_eq!(config.analysis.strength_thresholds, (0.2, 0.5));
+        assert!(parse_args(&args(&["--weak-threshold", "0.8"])).is_err());
+        assert!(parse_args(&args(&["--strong-threshold", "1.5"])).is_err());
+    }
+
+    // Test R-squared from arbitrary predictions, including worse-than-mean ones
+    #[test]
+    fn test_r_squared_from_predictions() {
+        let actual = [1.0, 2.0, 3.0, 4.0];
+
+        assert_eq!(r_squared_from_predictions(&actual, &actual), 1.0);
+        assert_eq!(r_squared_from_predictions(&actual, &[2.5; 4]), 0.0);
+        // SS_res = 20 against SS_tot = 5.
+        assert!((r_squared_from_predictions(&actual, &[4.0, 3.0, 2.0, 1.0]) + 3.0).abs() < 1e-12);
+        assert!(r_squared_from_predictions(&[2.0; 3], &[1.0, 2.0, 3.0]).is_nan());
+    }
+
+    // Test error handling in linear regression
+    #[test]
+    #[should_panic(expected = "Input vectors must be of equal length")]
+    fn test_linear_regression_different_lengths() {
+        let x = vec![1.0, 2.0, 3.0];
+        let y = vec![1.0, 2.0, 3.0, 4.0];
+        
+        calculate_linear_regression(&x, &y);
+    }
+
+    // Test RMSE and MAE against hand-computed values
+    #[test]
+    fn test_rmse_and_mae() {
+        let predicted = [1.0, 2.0, 3.0, 4.0];
+        let actual = [2.0, 2.0, 1.0, 4.0];
+
+        assert!((rmse(&predicted, &actual) - 1.25_f64.sqrt()).abs() < 1e-6);
+        assert!((mae(&predicted, &actual) - 0.75).abs() < 1e-6);
+    }
+
+    // Test CSV export keeps full precision
+    #[test]
+    fn test_write_results_csv() {
+        let path = std::env::temp_dir().join("finalproject_test_results.csv");
+        let path = path.to_str().unwrap();
+        let results = vec![CorrelationResult {
+            title: "Salary vs Age".to_string(),
+            response: "Salary",
+            log_target: false,
+            slope: 0.123456789,
+            intercept: 1.0,
+            slope_std_error: 0.1,
+            std_error_of_estimate: 2.0,
+            prediction: None,
+            confidence_level: 0.95,
+            slope_ci: (-0.1, 0.3),
+            weighted_fit: None,
+            covariance: 0.5,
+            correlation: -0.5,
+            rank_based: false,
+            strength: "Moderate",
+            bootstrap_ci: None,
+            spearman: -0.4,
+            theil_sen: (0.1, 1.1),
+            theil_sen_sampled: false,
+            huber: (0.1, 1.0),
+            huber_iterations: 3,
+            kendall: None,
+            r_squared: 0.25,
+            adjusted_r_squared: 0.2,
+            t_statistic: -1.5,
+            p_value: 0.2,
+            rmse: 2.0,
+            mae: 1.5,
+            residual_stats: compute_stats(&[-1.0, 1.0]),
+            test_r_squared: None,
+            test_rmse: None,
+        }];
+
+        write_results_csv(path, &results, b',').unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            written,
+            "analysis,slope,intercept,correlation,r_squared\nSalary vs Age,0.123456789,1,-0.5,0.25\n"
+        );
+    }
+
+    // Test the predictions dump has the comment line and fitted values
+    #[test]
+    fn test_write_predictions_csv() {
+        let path = std::env::temp_dir().join("finalproject_test_predictions.csv");
+        let path = path.to_str().unwrap();
+        let train = sample_individuals(3);
+        let config = parse_args(&args(&["--dump-predictions", path, "--dump-feature", "experience"])).unwrap();
+        let results = compute_correlations(&Dataset(train.clone()), &Dataset::default(), &config.analysis);
+
+        write_predictions_csv(path, &train, &results, &config.analysis, Field::Experience, b',').unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], "# analysis: Salary vs Years of Experience");
+        assert_eq!(lines[1], "x,actual_y,predicted_y,residual");
+        assert_eq!(lines.len(), 5);
+        assert!(lines[3].starts_with("1,51000,"));
+
+        assert_eq!(parse_args(&args(&["--dump-predictions", path])).unwrap().predictions_feature, Some(Field::Age));
+        assert!(parse_args(&args(&["--dump-feature", "age"])).is_err());
+        assert!(parse_args(&args(&["--dump-predictions", path, "--dump-feature", "salary"])).is_err());
+    }
+
+    // Test --jsonl lines are one self-contained object per individual
+    #[test]
+    fn test_predictions_jsonl() {
+        let sample = sample_individuals(3);
+        let config = parse_args(&args(&["--jsonl", "--dump-feature", "experience"])).unwrap();
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &config.analysis);
+
+        let lines = predictions_jsonl(&sample, &results, &config.analysis, Field::Experience);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("{\"id\":1,\"analysis\":\"Salary vs Years of Experience\",\"x\":1,\"actual\":51000,\"predicted\":"));
+        assert!(lines.iter().all(|line| line.ends_with('}') && !line.contains('\n')));
+        assert!(parse_args(&args(&["--json", "--jsonl"])).is_err());
+        assert!(parse_args(&args(&["stats", "--jsonl"])).is_err());
+    }
+
+    // Test JSON encoding of strings and non-finite numbers
+    #[test]
+    fn test_json_helpers() {
+        assert_eq!(json_string("a \"b\"\\c\n"), "\"a \\\"b\\\"\\\\c\\n\"");
+        assert_eq!(json_number(1.5), "1.5");
+        assert_eq!(json_number(f64::NAN), "null");
+        assert_eq!(json_number(f64::INFINITY), "null");
+    }
+
+    // Test the correlation matrix is symmetric with a unit diagonal
+    #[test]
+    fn test_correlation_matrix_symmetric() {
+        let individuals: Vec<Individual> = sample_individuals(20)
+            .into_iter()
+            .map(|mut ind| {
+                ind.job_satisfaction = (ind.id * 7 % 10) as f64;
+                ind.professional_network_size = (ind.id * 3 % 5) as f64;
+                ind
+            })
+            .collect();
+
+        let matrix = correlation_matrix(&individuals);
+
+        assert_eq!(matrix.len(), 7);
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row.len(), 7);
+            assert!((row[i] - 1.0).abs() < 1e-12, "Diagonal should be 1");
+            for (j, value) in row.iter().enumerate() {
+                assert_eq!(*value, matrix[j][i], "Matrix should be symmetric");
+            }
+        }
+        // Age and salary are both linear in id in the fixture.
+        assert!((matrix[0][5] - 1.0).abs() < 1e-9);
+    }
+
+    // Test t-test p-values against Student's t table values
+    #[test]
+    fn test_t_test_p_value() {
+        assert!((regularized_incomplete_beta(2.0, 2.0, 0.5) - 0.5).abs() < 1e-12);
+        assert!((t_test_p_value(2.228_138_85, 10.0) - 0.05).abs() < 1e-6);
+        assert!((t_test_p_value(2.0, 10.0) - 0.073_388_9).abs() < 1e-6);
+        assert!((t_test_p_value(0.0, 5.0) - 1.0).abs() < 1e-12);
+        assert_eq!(t_test_p_value(f64::INFINITY, 5.0), 0.0);
+    }
+
+    // Test the correlation t statistic, including perfect correlations
+    #[test]
+    fn test_correlation_t_statistic() {
+        assert!((correlation_t_statistic(0.6, 12) - 0.6 * (10.0_f64 / 0.64).sqrt()).abs() < 1e-12);
+        assert_eq!(correlation_t_statistic(1.0, 10), f64::INFINITY);
+        assert_eq!(correlation_t_statistic(-1.0, 10), f64::NEG_INFINITY);
+        assert!(correlation_t_statistic(0.5, 2).is_nan());
+    }
+
+    // Test slope standard error and CI on a textbook example
+    // (x = 1..5, y = 2,4,5,4,5: slope 0.6, SE 0.2828, t(0.975, 3) = 3.1824)
+    #[test]
+    fn test_slope_confidence_interval() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [2.0, 4.0, 5.0, 4.0, 5.0];
+
+        let se = slope_standard_error(&x, &y, 0.6, 2.2);
+        assert!((se - 0.08_f64.sqrt()).abs() < 1e-9, "SE should be sqrt(0.8 / 10)");
+
+        let t = t_critical_value(0.95, 3.0);
+        assert!((t - 3.182_446).abs() < 1e-5, "t critical value for 3 df");
+        assert!((0.6 - t * se - (-0.300_1)).abs() < 1e-3);
+        assert!((0.6 + t * se - 1.500_1).abs() < 1e-3);
+        assert!((t_critical_value(0.95, 1e6) - 1.959_96).abs() < 1e-4);
+    }
+
+    // Test the standard error of the estimate on the same example
+    // (SS_res = 2.4 with 3 df) and its n <= 2 guard
+    #[test]
+    fn test_std_error_of_estimate() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [2.0, 4.0, 5.0, 4.0, 5.0];
+        assert!((std_error_of_estimate(&x, &y, 0.6, 2.2) - 0.8_f64.sqrt()).abs() < 1e-9);
+        assert!(std_error_of_estimate(&x[..2], &y[..2], 2.0, 0.0).is_nan());
+    }
+
+    // Test prediction intervals: t(0.975, 3) * s * sqrt(1 + 1/n) at the mean,
+    // widening with leverage away from it
+    #[test]
+    fn test_prediction_interval() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let line = FittedLine::new(&x, LinearModel { slope: 0.6, intercept: 2.2 }, 0.8_f64.sqrt());
+        let (low, high) = prediction_interval(&line, 3.0, 0.95);
+        let margin = t_critical_value(0.95, 3.0) * (0.8_f64 * 1.2).sqrt();
+        assert!((low - (4.0 - margin)).abs() < 1e-9);
+        assert!((high - (4.0 + margin)).abs() < 1e-9);
+
+        let (far_low, far_high) = prediction_interval(&line, 8.0, 0.95);
+        assert!(far_high - far_low > high - low);
+
+        let config = parse_args(&args(&["--predict-at", "30", "--precision", "6"])).unwrap();
+        assert_eq!(config.analysis.predict_at, Some(30.0));
+        assert_eq!(config.analysis.precision, Some(6));
+        assert!(parse_args(&args(&["--predict-at", "inf"])).is_err());
+    }
+
+    // Test rank conversion averages tied ranks
+    #[test]
+    fn test_rank_values_ties() {
+        let ranks = rank_values(&[10.0, 20.0, 20.0, 5.0, 20.0]);
+        assert_eq!(ranks, vec![2.0, 4.0, 4.0, 1.0, 4.0]);
+    }
+
+    // Test Spearman correlation on monotonic but nonlinear data with ties
+    #[test]
+    fn test_spearman_correlation() {
+        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = vec![1.0, 8.0, 27.0, 64.0, 125.0];
+        assert!((spearman_correlation(&x, &y) - 1.0).abs() < 1e-6);
+
+        let reversed: Vec<f64> = y.iter().rev().cloned().collect();
+        assert!((spearman_correlation(&x, &reversed) + 1.0).abs() < 1e-6);
+
+        // Ranks of y are [1, 2.5, 2.5, 4]; Pearson on the ranks gives 0.9487.
+        let tied = vec![1.0, 2.0, 2.0, 3.0];
+        assert!((spearman_correlation(&[1.0, 2.0, 3.0, 4.0], &tied) - 0.948683).abs() < 1e-6);
+    }
+
+    // Test multiple regression recovers exact coefficients of a noiseless plane
+    #[test]
+    fn test_multiple_linear_regression() {
+        let x1 = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let x2 = vec![2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let y: Vec<f64> = x1.iter().zip(&x2).map(|(a, b)| 3.0 + 2.0 * a - 1.5 * b).collect();
+
+        let coefficients = multiple_linear_regression(&[x1, x2], &y).unwrap();
+
+        assert_eq!(coefficients.len(), 3);
+        assert!((coefficients[0] - 3.0).abs() < 1e-6, "Intercept should be 3");
+        assert!((coefficients[1] - 2.0).abs() < 1e-6, "First coefficient should be 2");
+        assert!((coefficients[2] + 1.5).abs() < 1e-6, "Second coefficient should be -1.5");
+    }
+
+    // Test ridge shrinks slopes but not the intercept, and handles collinearity
+    #[test]
+    fn test_ridge_regression() {
+        let x1 = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let x2 = vec![2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let y: Vec<f64> = x1.iter().zip(&x2).map(|(a, b)| 3.0 + 2.0 * a - 1.5 * b).collect();
+        let mean_y = y.iter().sum::<f64>() / y.len() as f64;
+
+        let ols = multiple_linear_regression(&[x1.clone(), x2.clone()], &y).unwrap();
+        let unpenalized = ridge_regression(&[x1.clone(), x2.clone()], &y, 0.0).unwrap();
+        for (a, b) in ols.iter().zip(&unpenalized) {
+            assert!((a - b).abs() < 1e-9);
+        }
+
+        let heavy = ridge_regression(&[x1.clone(), x2], &y, 1e9).unwrap();
+        assert!(heavy[1].abs() < 1e-6 && heavy[2].abs() < 1e-6);
+        assert!((heavy[0] - mean_y).abs() < 1e-4, "intercept {} should approach the mean", heavy[0]);
+
+        // Duplicate columns are singular for OLS but fine with a penalty; the
+        // weight is split evenly between them.
+        let doubled: Vec<f64> = x1.iter().map(|v| 2.0 * v).collect();
+        assert!(ridge_regression(&[x1.clone(), x1.clone()], &doubled, 0.0).is_none());
+        let split = ridge_regression(&[x1.clone(), x1], &doubled, 1e-3).unwrap();
+        assert!((split[1] - 1.0).abs() < 1e-3 && (split[2] - 1.0).abs() < 1e-3);
+    }
+
+    // Test the R-squared returned alongside a multiple regression fit
+    #[test]
+    fn test_fit_multiple_regression() {
+        let x1 = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let x2 = vec![2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let interaction: Vec<f64> = x1.iter().zip(&x2).map(|(a, b)| a * b).collect();
+        let y: Vec<f64> = (0..6).map(|i| 1.0 + x1[i] + 0.5 * interaction[i]).collect();
+
+        let (_, main_effects_r_squared) = fit_multiple_regression(&[x1.clone(), x2.clone()], &y).unwrap();
+        let (coefficients, r_squared) = fit_multiple_regression(&[x1, x2, interaction], &y).unwrap();
+
+        assert!(main_effects_r_squared < 1.0);
+        assert!((r_squared - 1.0).abs() < 1e-9);
+        assert!((coefficients[3] - 0.5).abs() < 1e-6);
+    }
+
+    // Test polynomial regression recovers an exact quadratic
+    #[test]
+    fn test_polynomial_regression_quadratic() {
+        let x: Vec<f64> = (0..10).map(|v| v as f64).collect();
+        let y: Vec<f64> = x.iter().map(|v| 1.0 - 2.0 * v + 0.5 * v * v).collect();
+
+        let coefficients = polynomial_regression(&x, &y, 2).unwrap();
+
+        assert_eq!(coefficients.len(), 3);
+        assert!((coefficients[0] - 1.0).abs() < 1e-6);
+        assert!((coefficients[1] + 2.0).abs() < 1e-6);
+        assert!((coefficients[2] - 0.5).abs() < 1e-6);
+        assert!((evaluate_polynomial(&coefficients, 4.0) - 1.0).abs() < 1e-6);
+    }
+
+    // Test a degree at or above the number of distinct x values is skipped
+    // rather than panicking
+    #[test]
+    fn test_polynomial_regression_too_few_distinct_x() {
+        let x = vec![1.0, 2.0, 2.0, 3.0, f64::NAN];
+        assert_eq!(distinct_finite_count(&x), 3);
+        assert!(polynomial_regression(&x[..4], &[1.0, 2.0, 2.0, 3.0], 3).is_none());
+
+        perform_polynomial_regression(&sample_individuals(5), 30, None);
+    }
+
+    // Test OLS residuals average to zero and match observed minus fitted
+    #[test]
+    fn test_residuals_mean_zero() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [2.0, 4.5, 5.5, 8.5, 9.0];
+        let (slope, intercept, _, _) = calculate_linear_regression(&x, &y);
+
+        let residuals = residuals(&x, &y, slope, intercept);
+
+        assert_eq!(residuals.len(), 5);
+        assert!((residuals[0] - (2.0 - (slope + intercept))).abs() < 1e-12);
+        assert!(compute_stats(&residuals).mean.abs() < 1e-10);
+    }
+
+    // Test skewness and excess kurtosis on symmetric and skewed fixtures
+    #[test]
+    fn test_skewness_and_kurtosis() {
+        let symmetric = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!(skewness(&symmetric).abs() < 1e-12);
+        // m2 = 2, m4 = 6.8, so 6.8 / 4 - 3.
+        assert!((kurtosis(&symmetric) + 1.3).abs() < 1e-12);
+
+        let right_skewed = [1.0, 1.0, 1.0, 1.0, 10.0];
+        assert!(skewness(&right_skewed) > 1.0);
+
+        assert!(skewness(&[1.0, 2.0]).is_nan());
+        assert!(kurtosis(&[1.0, 2.0, 3.0]).is_nan());
+        assert!(skewness(&[4.0; 5]).is_nan());
+    }
+
+    // Test the mode, including the smallest-value tie-break
+    #[test]
+    fn test_mode() {
+        assert_eq!(mode(&[2.0, 1.0, 2.0, 3.0]), Some(2.0));
+        assert_eq!(mode(&[3.0, 1.0, 3.0, 1.0, 2.0]), Some(1.0));
+        assert_eq!(mode(&[5.0]), Some(5.0));
+        assert_eq!(mode(&[]), None);
+    }
+
+    // Test percentile ranks inside and outside the data range
+    #[test]
+    fn test_percentile_rank() {
+        let data = [10.0, 20.0, 20.0, 30.0, 40.0];
+
+        assert_eq!(percentile_rank(&data, 5.0), 0.0);
+        assert_eq!(percentile_rank(&data, 10.0), 20.0);
+        assert_eq!(percentile_rank(&data, 25.0), 60.0);
+        assert_eq!(percentile_rank(&data, 40.0), 100.0);
+        assert_eq!(percentile_rank(&data, 1e9), 100.0);
+        assert!(percentile_rank(&[], 1.0).is_nan());
+    }
+
+    // Test age bands are contiguous from the youngest age, including empty ones
+    #[test]
+    fn test_age_bands() {
+        let mut sample = sample_individuals(3);
+        sample[0].age = 20.0;
+        sample[1].age = 24.0;
+        sample[2].age = 36.0;
+
+        let bands = age_bands(&sample, 5.0);
+
+        let starts: Vec<f64> = bands.iter().map(|(start, _, _)| *start).collect();
+        let counts: Vec<usize> = bands.iter().map(|(_, count, _)| *count).collect();
+        assert_eq!(starts, vec![20.0, 25.0, 30.0, 35.0]);
+        assert_eq!(counts, vec![2, 0, 0, 1]);
+        assert_eq!(bands[0].2, 50_500.0);
+        assert!(bands[1].2.is_nan());
+        assert!(parse_args(&args(&["--age-band", "0"])).is_err());
+
+        sample[1].age = f64::NAN;
+        sample[2].age = f64::INFINITY;
+        let bands = age_bands(&sample, 5.0);
+        assert_eq!(bands, vec![(20.0, 1, 50_000.0)]);
+
+        assert_eq!(age_band_lines(&sample, 5.0, None), vec!["20-25: count 1, mean salary 50000.00"]);
+        assert_eq!(age_band_lines(&sample, 5.0, Some(0)), vec!["20-25: count 1, mean salary 50000"]);
+    }
+
+    // Test histogram bucketing, including the max value and equal values
+    #[test]
+    fn test_histogram() {
+        let (min, width, counts) = histogram(&[0.0, 1.0, 2.5, 9.9, 10.0], 4);
+        assert_eq!(min, 0.0);
+        assert_eq!(width, 2.5);
+        assert_eq!(counts, vec![2, 1, 0, 2]);
+
+        let (_, width, counts) = histogram(&[3.0, 3.0, 3.0], 5);
+        assert_eq!(width, 0.0);
+        assert_eq!(counts, vec![3, 0, 0, 0, 0]);
+    }
+
+    // Test weighted regression: equal weights match OLS, zero weights drop points
+    #[test]
+    fn test_weighted_linear_regression() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [2.0, 4.5, 5.5, 8.5, 9.0];
+        let (slope, intercept, _, _) = calculate_linear_regression(&x, &y);
+
+        let (w_slope, w_intercept) = weighted_linear_regression(&x, &y, &[2.0; 5]);
+        assert!((w_slope - slope).abs() < 1e-10);
+        assert!((w_intercept - intercept).abs() < 1e-10);
+
+        // Zeroing the off-line point leaves the exact line y = 2x + 1.
+        let y = [3.0, 5.0, 7.0, 100.0, 11.0];
+        let (w_slope, w_intercept) = weighted_linear_regression(&x, &y, &[1.0, 1.0, 1.0, 0.0, 1.0]);
+        assert!((w_slope - 2.0).abs() < 1e-10);
+        assert!((w_intercept - 1.0).abs() < 1e-10);
+    }
+
+    // Test k-fold CV scores a noiseless line perfectly in every fold
+    #[test]
+    fn test_k_fold_cv_exact_line() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut individuals = sample_individuals(23);
+        for ind in individuals.iter_mut() {
+            ind.salary = 30_000.0 + 2_500.0 * ind.years_of_experience;
+        }
+
+        let (mean, std_dev) = k_fold_cv(&individuals, 5, Field::Experience, Field::Salary, &mut rng);
+
+        assert!((mean - 1.0).abs() < 1e-9);
+        assert!(std_dev.abs() < 1e-9);
+    }
+
+    // Test the bootstrap interval brackets the correlation and is reproducible
+    #[test]
+    fn test_bootstrap_correlation_ci() {
+        let x: Vec<f64> = (0..50).map(|i| i as f64).collect();
+        let y: Vec<f64> = x.iter().map(|v| v + ((v * 7.0) % 11.0)).collect();
+        let correlation = pearson_correlation(&x, &y);
+
+        let (low, high) = bootstrap_correlation_ci(&x, &y, 500, &mut StdRng::seed_from_u64(1));
+        let again = bootstrap_correlation_ci(&x, &y, 500, &mut StdRng::seed_from_u64(1));
+
+        assert!(low < correlation && correlation < high);
+        assert!(high <= 1.0);
+        assert_eq!((low, high), again);
+
+        let (low, high) = bootstrap_correlation_ci(&x, &x, 100, &mut StdRng::seed_from_u64(2));
+        assert!((low - 1.0).abs() < 1e-9 && (high - 1.0).abs() < 1e-9);
+    }
+
+    // Test the Huber fit downweights a gross outlier that drags the OLS slope
+    #[test]
+    fn test_huber_regression_outlier() {
+        let x: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let mut y: Vec<f64> = x.iter().map(|xi| 3.0 * xi + 1.0 + if xi % 2.0 == 0.0 { 0.5 } else { -0.5 }).collect();
+        y[19] = 500.0;
+
+        let (ols_slope, _, _, _) = calculate_linear_regression(&x, &y);
+        let (slope, intercept, iterations) = huber_regression(&x, &y, 1.0, HUBER_MAX_ITERATIONS);
+
+        assert!((ols_slope - 3.0).abs() > 2.0);
+        assert!((slope - 3.0).abs() < 0.1, "slope = {}", slope);
+        assert!((intercept - 1.0).abs() < 1.0, "intercept = {}", intercept);
+        assert!(iterations > 0 && iterations < HUBER_MAX_ITERATIONS);
+
+        // A non-positive delta is just the OLS fit.
+        assert_eq!(huber_regression(&x, &y, 0.0, 10).2, 0);
+        assert!((robust_scale(&[1.0, 2.0, 3.0, 4.0, 100.0]) - 1.0 / 0.6745).abs() < 1e-12);
+    }
+
+    // Test Theil-Sen ignores a gross outlier that drags the OLS slope
+    #[test]
+    fn test_theil_sen_outlier() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let y = [3.0, 5.0, 7.0, 9.0, 11.0, 13.0, 500.0];
+
+        let (slope, intercept) = theil_sen(&x, &y);
+        let (ols_slope, _, _, _) = calculate_linear_regression(&x, &y);
+
+        assert!((slope - 2.0).abs() < 1e-12);
+        assert!((intercept - 1.0).abs() < 1e-12);
+        assert!(ols_slope > 40.0);
+        assert!(theil_sen(&[2.0; 3], &[1.0, 2.0, 3.0]).0.is_nan());
+        assert!(theil_sen_samples_pairs(1_000));
+        assert!(!theil_sen_samples_pairs(100));
+    }
+
+    // Test Kendall's tau-b on concordant, reversed and tied data
+    #[test]
+    fn test_kendall_tau() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!((kendall_tau(&x, &[10.0, 20.0, 30.0, 40.0, 50.0]) - 1.0).abs() < 1e-12);
+        assert!((kendall_tau(&x, &[5.0, 4.0, 3.0, 2.0, 1.0]) + 1.0).abs() < 1e-12);
+
+        // 5 concordant, 0 discordant, 1 tie in y out of 6 pairs: 5 / sqrt(6 * 5).
+        let tied = [1.0, 2.0, 2.0, 3.0];
+        let expected = 5.0 / 30.0_f64.sqrt();
+        assert!((kendall_tau(&[1.0, 2.0, 3.0, 4.0], &tied) - expected).abs() < 1e-12);
+
+        assert!(kendall_tau(&x, &[1.0; 5]).is_nan());
+    }
+
+    // Test adjusted R-squared formula and its small-sample guard
+    #[test]
+    fn test_adjusted_r_squared() {
+        // 1 - (1 - 0.5) * 9 / 7
+        assert!((adjusted_r_squared(0.5, 10, 2) - (1.0 - 0.5 * 9.0 / 7.0)).abs() < 1e-12);
+        assert!((adjusted_r_squared(1.0, 10, 3) - 1.0).abs() < 1e-12);
+        assert!(adjusted_r_squared(0.5, 3, 2).is_nan());
+    }
+
+    // Test that perfectly collinear features, and fewer rows than
+    // coefficients, are reported as singular instead of panicking
+    #[test]
+    fn test_multiple_linear_regression_singular() {
+        let x1 = vec![1.0, 2.0, 3.0, 4.0];
+        let x2: Vec<f64> = x1.iter().map(|v| v * 2.0).collect();
+        let y = vec![1.0, 2.0, 3.0, 4.0];
+
+        assert!(multiple_linear_regression(&[x1.clone(), x2], &y).is_none());
+        let x3 = vec![4.0, 1.0, 3.0, 2.0];
+        let x4 = vec![0.0, 1.0, 1.0, 0.0];
+        let x5 = vec![2.0, 2.0, 1.0, 5.0];
+        assert!(fit_multiple_regression(&[x1, x3, x4, x5], &y).is_none());
+        perform_multiple_regression(&sample_individuals(3), true, Some(1.0), false, None);
+    }
+
+    fn sample_individuals(n: usize) -> Vec<Individual> {
+        (0..n)
+            .map(|i| Individual {
+                id: i,
+                age: 20.0 + i as f64,
+                years_of_experience: i as f64,
+                job_satisfaction: 5.0,
+                professional_network_size: 10.0,
+                family_influence: (i % 4) as f64,
+                salary: 50_000.0 + 1_000.0 * i as f64,
+                likelihood_to_change_occupation: (i % 2) as f64,
+                external: f64::NAN,
+                imputed: false,
+            })
+            .collect()
+    }
+
+    // Test reservoir sampling size, distinctness, and rough uniformity
+    #[test]
+    fn test_reservoir_sample() {
+        let mut rng = StdRng::seed_from_u64(11);
+
+        let sample = reservoir_sample(sample_individuals(100).into_iter(), 10, &mut rng);
+        let mut ids: Vec<usize> = sample.iter().map(|ind| ind.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 10, "Sample should hold 10 distinct individuals");
+
+        let all = reservoir_sample(sample_individuals(5).into_iter(), 10, &mut rng);
+        assert_eq!(all.len(), 5, "Short inputs are returned whole");
+
+        // Each of 20 items should be picked about 5 / 20 of the time.
+        let mut counts = [0usize; 20];
+        for _ in 0..4_000 {
+            for ind in reservoir_sample(sample_individuals(20).into_iter(), 5, &mut rng) {
+                counts[ind.id] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&c| (850..=1150).contains(&c)), "{:?}", counts);
+    }
+
+    // Test the --head preview decodes family influence and stops at n rows
+    #[test]
+    fn test_head_table() {
+        let lines = head_table(&sample_individuals(5), 2);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].trim_start().starts_with("ID"));
+        let fields: Vec<&str> = lines[2].split_whitespace().collect();
+        assert_eq!(fields, vec!["1", "21", "1", "5", "10", "Low", "51000", "1"]);
+        assert_eq!(parse_args(&args(&["--head", "3"])).unwrap().head, Some(3));
+    }
+
+    // Test --join attaches values by id, drops unmatched ids, and analyzes them
+    #[test]
+    fn test_join_external() {
+        let path = std::env::temp_dir().join("finalproject_test_join.csv");
+        std::fs::write(&path, "id,region\n0,1.5\n2,2.5\nx,3\n3,4.5\n3,5.5\n").unwrap();
+        let values = read_join_column(path.to_str().unwrap(), b',').unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(values.len(), 3);
+        let (joined, dropped) = join_external(sample_individuals(4), &values);
+        assert_eq!(dropped, 1);
+        let pairs: Vec<(usize, f64)> = joined.iter().map(|ind| (ind.id, ind.external)).collect();
+        assert_eq!(pairs, vec![(0, 1.5), (2, 2.5), (3, 5.5)]);
+
+        assert_eq!(selected_analyses(&AnalysisOptions::default()).len(), 6);
+        let config = parse_args(&args(&["--join", "regions.csv"])).unwrap();
+        assert_eq!(selected_analyses(&config.analysis).last(), Some(&Field::External));
+        assert!(parse_args(&args(&["--features", "external"])).is_err());
+    }
+
+    // Test weighted sampling skips non-positive weights and favors heavy ones
+    #[test]
+    fn test_weighted_sample() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let individuals = sample_individuals(10);
+
+        // Only ids 1 and up have a positive weight.
+        let sample = weighted_sample(&individuals, 20, |ind| ind.years_of_experience, &mut rng);
+        assert_eq!(sample.len(), 9);
+        assert!(sample.iter().all(|ind| ind.id != 0));
+
+        // Id 1 weighs 9 times as much as id 2 and should win about 90% of draws.
+        let mut heavy = 0;
+        for _ in 0..2_000 {
+            let sample = weighted_sample(&individuals[1..3], 1, |ind| if ind.id == 1 { 9.0 } else { 1.0 }, &mut rng);
+            if sample[0].id == 1 {
+                heavy += 1;
+            }
+        }
+        assert!((1_700..=1_900).contains(&heavy), "{}", heavy);
+    }
+
+    // Test the stratified sample hits its size and keeps the level shares
+    #[test]
+    fn test_stratified_sample() {
+        let mut rng = StdRng::seed_from_u64(9);
+        // Levels 0-3 each hold a quarter of the 200 records.
+        let individuals = sample_individuals(200);
+
+        let sample = stratified_sample(&individuals, 41, &mut rng);
+        assert_eq!(sample.len(), 41);
+        let counts: Vec<usize> = (0..4)
+            .map(|level| sample.iter().filter(|ind| ind.family_influence == level as f64).count())
+            .collect();
+        assert!(counts.iter().all(|&c| c == 10 || c == 11), "{:?}", counts);
+
+        let mut ids: Vec<usize> = sample.iter().map(|ind| ind.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 41);
+
+        assert_eq!(stratified_sample(&individuals, 500, &mut rng).len(), 200);
+    }
+
+    // Test train/test split sizes and that no record is lost or duplicated
+    #[test]
+    fn test_train_test_split() {
+        let individuals = sample_individuals(50);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let (train, test) = train_test_split(&individuals, 0.2, &mut rng);
+
+        assert_eq!(train.len(), 40);
+        assert_eq!(test.len(), 10);
+        let mut ids: Vec<usize> = train.iter().chain(&test).map(|ind| ind.id).collect();
+        ids.sort();
+        assert_eq!(ids, (0..50).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Test fraction must be between 0 and 1")]
+    fn test_train_test_split_invalid_fraction() {
+        let mut rng = StdRng::seed_from_u64(1);
+        train_test_split(&sample_individuals(10), 1.5, &mut rng);
+    }
+
+    // Test logistic regression learns a separable one-feature problem
+    #[test]
+    fn test_logistic_regression_separable() {
+        let x = vec![-2.0, -1.5, -1.0, -0.5, 0.5, 1.0, 1.5, 2.0];
+        let y = vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+
+        let weights = logistic_regression(std::slice::from_ref(&x), &y, 0.5, 2_000);
+
+        assert!(weights[1] > 0.0, "Weight should be positive");
+        for (xi, yi) in x.iter().zip(&y) {
+            let p = sigmoid(weights[0] + weights[1] * xi);
+            assert_eq!(if p >= 0.5 { 1.0 } else { 0.0 }, *yi);
+        }
+    }
+
+    // Test Individual struct creation
+    #[test]
+    fn test_individual_creation() {
+        let individual = Individual {
+            id: 1,
+            age: 30.0,
+            years_of_experience: 5.0,
+            job_satisfaction: 4.5,
+            professional_network_size: 100.0,
+            family_influence: 2.0,
+            salary: 75000.0,
+            likelihood_to_change_occupation: 0.3,
+            external: f64::NAN,
+            imputed: false,
+        };
+
+        assert_eq!(individual.id, 1);
+        assert_eq!(individual.age, 30.0);
+        assert_eq!(individual.family_influence, 2.0);
+    }
+
+    // Test family influence parsing
+    #[test]
+    fn test_family_influence_parsing() {
+        let test_cases = vec![
+            ("None", Some(0.0)),
+            ("Low", Some(1.0)),
+            ("Medium", Some(2.0)),
+            ("High", Some(3.0)),
+            (" Very High ", Some(4.0)),
+            ("low", Some(1.0)),
+            (" HIGH ", Some(3.0)),
+            ("med", Some(2.0)),
+            ("very_high", Some(4.0)),
+            ("Very-High", Some(4.0)),
+            ("Extreme", None),
+            ("", None),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(family_influence_value(input), expected);
+        }
+    }
+
+    // Test reading a small CSV dataset
+    #[test]
+    fn test_read_small_dataset() {
+        let csv_data = 
+"Age,Experience,Job Satisfaction,Network Size,Family Influence,Salary,Likelihood to Change
+30,5,4.5,100,Medium,75000,0.3
+35,10,4.8,200,High,85000,0.2
+25,2,3.9,50,Low,60000,0.5";
+
+        let cursor = Cursor::new(csv_data.as_bytes());
+        
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(cursor);
+
+        let individuals: Vec<Individual> = rdr
+            .records()
+            .enumerate()
+            .filter_map(|(i, result)| {
+                let record = result.ok()?;
+                
+                let age = record.get(0)?.trim().parse::<f64>().ok()?;
+                let years_of_experience = record.get(1)?.trim().parse::<f64>().ok()?;
+                let job_satisfaction = record.get(2)?.trim().parse::<f64>().ok()?;
+                let professional_network_size = record.get(3)?.trim().parse::<f64>().ok()?;
+                
+                let family_influence = family_influence_value(record.get(4)?)?;
+                
+                let salary = record.get(5)?.trim().parse::<f64>().ok()?;
+                let likelihood_to_change_occupation = record.get(6)?.trim().parse::<f64>().ok()?;
+
+                Some(Individual {
+                    id: i,
+                    age,
+                    years_of_experience,
+                    job_satisfaction,
+                    professional_network_size,
+                    family_influence,
+                    salary,
+                    likelihood_to_change_occupation,
+                    external: f64::NAN,
+                    imputed: false,
+                })
+            })
+            .collect();
+
+        assert_eq!(individuals.len(), 3, "Should parse 3 records");
+        assert_eq!(individuals[0].age, 30.0);
+        assert_eq!(individuals[1].salary, 85000.0);
+        assert_eq!(individuals[2].family_influence, 1.0);
+    }
+
+    // Test print_stats function
+    #[test]
+    fn test_print_stats() {
+        let test_data = [10.0, 20.0, 30.0, 40.0, 50.0];
+        
+        let stats = compute_stats(&test_data);
+        
+        assert_eq!(stats.count, 5);
+        assert!((stats.mean - 30.0).abs() < 1e-6, "Mean should be 30");
+        assert!((stats.min - 10.0).abs() < 1e-6, "Min should be 10");
+        assert!((stats.max - 50.0).abs() < 1e-6, "Max should be 50");
+        assert!((stats.median - 30.0).abs() < 1e-6, "Median should be 30");
+        assert!((stats.variance - 250.0).abs() < 1e-6, "Variance should be 250");
+        assert!((stats.std_dev - 250.0_f64.sqrt()).abs() < 1e-6);
+    }
+
+    // Test IQR outlier detection flags only the extreme values
+    #[test]
+    fn test_outliers_iqr() {
+        let data = [-50.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 100.0];
+
+        // Q1 = 2.25, Q3 = 6.75, IQR = 4.5
+        let (lower, upper) = iqr_fences(&data).unwrap();
+        assert!((lower + 4.5).abs() < 1e-9);
+        assert!((upper - 13.5).abs() < 1e-9);
+        assert_eq!(outliers_iqr(&data), vec![0, 9]);
+        assert!(outliers_iqr(&[]).is_empty());
+    }
+
+    // Test salary trimming drops the extreme rows but keeps their neighbours' ids
+    #[test]
+    fn test_trim_salary_outliers() {
+        let mut individuals = sample_individuals(10);
+        individuals[3].salary = 10_000_000.0;
+
+        let (kept, dropped) = trim_salary_outliers(individuals);
+
+        assert_eq!(dropped, 1);
+        assert_eq!(kept.len(), 9);
+        assert!(kept.iter().all(|ind| ind.id != 3));
+    }
+
+    // Test z-score normalization and its zero-variance guard
+    #[test]
+    fn test_zscore_normalize() {
+        let normalized = zscore_normalize(&[2.0, 4.0, 6.0]);
+        assert_eq!(normalized, vec![-1.0, 0.0, 1.0]);
+
+        let constant = [5.0, 5.0, 5.0];
+        assert_eq!(zscore_normalize(&constant), constant.to_vec());
+    }
+
+    // Test min-max scaling and its constant-column guard
+    #[test]
+    fn test_minmax_scale() {
+        assert_eq!(minmax_scale(&[10.0, 15.0, 20.0]), vec![0.0, 0.5, 1.0]);
+        assert_eq!(minmax_scale(&[3.0, 3.0]), vec![0.0, 0.0]);
+        assert_eq!(Scaling::parse("minmax"), Ok(Scaling::MinMax));
+        assert!(Scaling::parse("log").is_err());
+    }
+
+    // Test that scaling rewrites predictors but leaves salary alone
+    #[test]
+    fn test_scale_features() {
+        let individuals = sample_individuals(5);
+        let scaled = scale_features(&individuals, Scaling::ZScore);
+
+        let ages: Vec<f64> = scaled.iter().map(|ind| ind.age).collect();
+        assert!(compute_stats(&ages).mean.abs() < 1e-9);
+        assert!((compute_stats(&ages).std_dev - 1.0).abs() < 1e-9);
+        for (before, after) in individuals.iter().zip(&scaled) {
+            assert_eq!(before.salary, after.salary);
+            assert_eq!(before.id, after.id);
+        }
+    }
+
+    // Test k-means separates two well-separated blobs
+    #[test]
+    fn test_kmeans_two_blobs() {
+        let points: Vec<Vec<f64>> = (0..10)
+            .map(|i| {
+                let offset = if i < 5 { 0.0 } else { 100.0 };
+                vec![offset + i as f64 * 0.1, offset]
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let (assignments, centroids) = kmeans(&points, 2, 50, &mut rng);
+
+        assert_eq!(centroids.len(), 2);
+        assert!(assignments[..5].iter().all(|&c| c == assignments[0]));
+        assert!(assignments[5..].iter().all(|&c| c == assignments[5]));
+        assert_ne!(assignments[0], assignments[5]);
+    }
+
+    // Test compute_stats on degenerate inputs
+    #[test]
+    fn test_compute_stats_degenerate() {
+        let empty = compute_stats(&[]);
+        assert_eq!(empty.count, 0);
+        assert!(empty.mean.is_nan());
+
+        let single = compute_stats(&[7.0]);
+        assert_eq!(single.count, 1);
+        assert_eq!(single.median, 7.0);
+        assert!(single.std_dev.is_nan(), "Std dev is undefined for n = 1");
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    // Test command-line parsing of the path and sample size
+    #[test]
+    fn test_parse_args() {
+        let config = parse_args(&[]).unwrap();
+        assert_eq!(config.file_paths, vec!["career_dataset.csv"]);
+        assert_eq!(config.sample_size, 2_000);
+
+        let config = parse_args(&args(&["--sample-fraction", "0.1"])).unwrap();
+        assert_eq!(config.sample_fraction, Some(0.1));
+        assert_eq!(fraction_sample_size(0.1, 20_000), 2_000);
+        assert_eq!(fraction_sample_size(0.1, 3), 1);
+        assert_eq!(fraction_sample_size(1.0, 7), 7);
+        assert!(parse_args(&args(&["--sample-fraction", "0"])).is_err());
+        assert!(parse_args(&args(&["--sample-fraction", "1.5"])).is_err());
+        assert!(parse_args(&args(&["--sample-fraction", "0.5", "--sample-size", "10"])).is_err());
+
+        let config = parse_args(&args(&["data.csv", "--sample-size", "100"])).unwrap();
+        assert_eq!(config.file_paths, vec!["data.csv"]);
+        assert_eq!(config.sample_size, 100);
+
+        assert_eq!(config.seed, None);
+
+        let config = parse_args(&args(&["--seed", "42"])).unwrap();
+        assert_eq!(config.seed, Some(42));
+        assert!(!config.read_options.verbose);
+        assert!(parse_args(&args(&["--verbose"])).unwrap().read_options.verbose);
+
+        assert!(parse_args(&args(&["--test-fraction", "1.0"])).is_err());
+        assert!(parse_args(&args(&["--confidence", "95"])).is_err());
+        assert!(parse_args(&args(&["--sample-size"])).is_err());
+        assert!(parse_args(&args(&["--sample-size", "abc"])).is_err());
+        assert!(parse_args(&args(&["--bogus"])).is_err());
+    }
+
+    // Test --filter parses each operator and keeps only matching individuals
+    #[test]
+    fn test_filters() {
+        let filter = Filter::parse("age<22").unwrap();
+        assert_eq!(filter, Filter { field: Field::Age, op: Comparison::Lt, value: 22.0 });
+        assert_eq!(Filter::parse("salary >= 50000").unwrap().op, Comparison::Ge);
+        assert_eq!(Filter::parse("family_influence==3").unwrap().field, Field::Influence);
+        assert_eq!(Filter::parse("age<=40").unwrap().to_string(), "age<=40");
+        assert!(Filter::parse("age").is_err());
+        assert!(Filter::parse("age<old").is_err());
+        assert!(Filter::parse("height>1").is_err());
+
+        let filters = vec![Filter::parse("age>=21").unwrap(), Filter::parse("influence!=2").unwrap()];
+        let kept = apply_filters(sample_individuals(5), &filters);
+        let ids: Vec<usize> = kept.iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![1, 3, 4]);
+
+        let config = parse_args(&args(&["--filter", "age<40", "--filter", "salary>0"])).unwrap();
+        assert_eq!(config.filters.len(), 2);
+        assert!(parse_args(&args(&["--filter", "external>1"])).is_err());
+    }
+
+    // Test Dataset columns follow the Field selectors and names round-trip
+    #[test]
+    fn test_dataset_column() {
+        let dataset = Dataset(sample_individuals(3));
+        assert_eq!(dataset.column(Field::Age), vec![20.0, 21.0, 22.0]);
+        assert_eq!(dataset.column(Field::Salary).len(), dataset.len());
+        assert!(dataset.column(Field::External).iter().all(|x| x.is_nan()));
+
+        for field in Field::ALL {
+            assert_eq!(Field::from_name(field.name()), Some(field));
+        }
+        assert_eq!(Field::from_name("height"), None);
+    }
+
+    // Test --features filters the salary analyses and rejects unknown names
+    #[test]
+    fn test_selected_analyses() {
+        assert_eq!(selected_analyses(&AnalysisOptions::default()).len(), 6);
+
+        let config = parse_args(&args(&["--features", "experience, age"])).unwrap();
+        let titles: Vec<String> = selected_analyses(&config.analysis)
+            .iter()
+            .map(|field| config.analysis.title(field.label()))
+            .collect();
+        assert_eq!(titles, vec!["Salary vs Age", "Salary vs Years of Experience"]);
+
+        let err = parse_args(&args(&["--features", "age,height"])).unwrap_err();
+        assert!(err.contains("height") && err.contains("likelihood"));
+    }
+
+    // Test --target swaps the response and drops it from the predictors
+    #[test]
+    fn test_target_option() {
+        let config = parse_args(&args(&["--target", "likelihood"])).unwrap();
+        let names: Vec<&str> = selected_analyses(&config.analysis).iter().map(|field| field.name()).collect();
+        assert_eq!(names, vec!["salary", "age", "experience", "satisfaction", "network", "influence"]);
+
+        let results = compute_correlations(&Dataset(sample_individuals(8)), &Dataset::default(), &config.analysis);
+        assert_eq!(results[0].title, "Likelihood to Change Occupation vs Salary");
+        assert_eq!(results[0].response(), "Likelihood to Change Occupation");
+
+        assert!(parse_args(&args(&["--target", "height"])).is_err());
+        assert!(parse_args(&args(&["--target", "age", "--log-target"])).is_err());
+        assert!(parse_args(&args(&["--target", "age", "--features", "age"])).is_err());
+    }
+
+    // Test that a leading subcommand is recognized and combines with shared options
+    #[test]
+    fn test_parse_args_subcommand() {
+        assert_eq!(parse_args(&[]).unwrap().command, Command::All);
+
+        let config = parse_args(&args(&["correlate", "data.csv", "--seed", "7"])).unwrap();
+        assert_eq!(config.command, Command::Correlate);
+        assert_eq!(config.file_paths, vec!["data.csv"]);
+        assert_eq!(config.seed, Some(7));
+
+        // Only the first argument is treated as a subcommand.
+        let config = parse_args(&args(&["stats"])).unwrap();
+        assert_eq!(config.command, Command::Stats);
+        assert_eq!(config.file_paths, vec!["career_dataset.csv"]);
+        let config = parse_args(&args(&["data.csv", "stats"])).unwrap();
+        assert_eq!(config.command, Command::All);
+        assert_eq!(config.file_paths, vec!["data.csv", "stats"]);
+
+        // "-" is standard input, which can only be consumed once.
+        assert_eq!(parse_args(&args(&["-", "--seed", "1"])).unwrap().file_paths, vec![STDIN_PATH]);
+        assert!(parse_args(&args(&["-", "-"])).is_err());
+        assert!(parse_args(&args(&["-", "--join", "-"])).is_err());
+
+        assert!(parse_args(&args(&["regress", "--json"])).is_err());
+        assert!(parse_args(&args(&["cluster", "--stream"])).is_err());
+        assert!(Command::All.runs(Command::Cluster));
+        assert!(!Command::Stats.runs(Command::Regress));
+    }
+
+    const TEST_HEADER: &str = "Field of Study,Current Occupation,Age,Gender,Years of Experience,Education Level,Industry Growth Rate,Job Satisfaction,Work-Life Balance,Job Opportunities,Salary,Job Security,Career Change Interest,Skills Gap,Family Influence,Mentorship Available,Certifications,Freelancing Experience,Geographic Mobility,Professional Networks,Career Change Events,Technology Adoption,Likely to Change Occupation";
+
+    // Builds a full-width dataset row from the fields read_dataset uses.
+    fn dataset_row(age: &str, experience: &str, salary: &str, influence: &str) -> String {
+        format!(
+            "Medicine,Doctor,{},Male,{},Master's,High,7,5,50,{},5,0,3,{},0,0,0,1,2,0,1,0",
+            age, experience, salary, influence
+        )
+    }
+
+    fn write_temp_dataset(name: &str, rows: &[String]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut contents = String::from(TEST_HEADER);
+        for row in rows {
+            contents.push('\n');
+            contents.push_str(row);
+        }
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // Test that rows with missing numbers are dropped by default and imputed on request
+    #[test]
+    fn test_read_dataset_imputation() {
+        let rows = vec![
+            dataset_row("30", "5", "60000", "Low"),
+            dataset_row("40", "", "80000", "High"),
+            dataset_row("", "15", "100000", "Medium"),
+        ];
+        let path = write_temp_dataset("finalproject_test_impute.csv", &rows);
+        let path_str = path.to_str().unwrap();
+
+        let impute = ReadOptions {
+            impute: true,
+            ..ReadOptions::default()
+        };
+        let (dropped, _) = read_dataset(&[path_str], &ReadOptions::default()).unwrap();
+        let (imputed, _) = read_dataset(&[path_str], &impute).unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-    let max_records = 20_000;
-    let mut parse_errors = 0;
+        assert_eq!(dropped.len(), 1, "Incomplete rows should be dropped by default");
+        assert_eq!(imputed.len(), 3, "Incomplete rows should be kept when imputing");
+        assert_eq!(imputed[1].years_of_experience, 10.0, "Experience mean of 5 and 15");
+        assert_eq!(imputed[2].age, 35.0, "Age mean of 30 and 40");
+    }
 
-    for (i, result) in rdr.records().enumerate() {
-        if i >= max_records {
-            break;
-        }
+    // Test reading a semicolon-separated file with a configured delimiter
+    #[test]
+    fn test_read_dataset_delimiter() {
+        let row = dataset_row("30", "5", "60000", "Low").replace(',', ";");
+        let path = std::env::temp_dir().join("finalproject_test_semicolon.csv");
+        std::fs::write(&path, format!("{}\n{}", TEST_HEADER.replace(',', ";"), row)).unwrap();
 
-        let record = result?;
+        let options = ReadOptions {
+            delimiter: parse_delimiter("semicolon").unwrap(),
+            ..ReadOptions::default()
+        };
+        let (individuals, _) = read_dataset(&[path.to_str().unwrap()], &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-        if record.len() < 23 {
-            println!("Short record at index {}: {:?}", i, record);
-            parse_errors += 1;
-            continue;
-        }
+        assert_eq!(individuals.len(), 1);
+        assert_eq!(individuals[0].salary, 60000.0);
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert_eq!(parse_delimiter("|"), Ok(b'|'));
+        assert!(parse_delimiter("ab").is_err());
+    }
+
+    // Test streaming sums match the in-memory regression
+    #[test]
+    fn test_streaming_sums_match_batch() {
+        let rows: Vec<String> = (0..30)
+            .map(|i| {
+                dataset_row(
+                    &(20 + i % 17).to_string(),
+                    &(i % 11).to_string(),
+                    &(40_000 + i * 1_337 % 9_000).to_string(),
+                    "Medium",
+                )
+            })
+            .collect();
+        let path = write_temp_dataset("finalproject_test_stream.csv", &rows);
+        let path_str = path.to_str().unwrap();
+
+        let (individuals, _) = read_dataset(&[path_str], &ReadOptions::default()).unwrap();
+        let mut sums = RegressionSums::default();
+        let mut streamed = 0;
+        for_each_raw_row(path_str, &ReadOptions::default(), |row| {
+            let ind = individual_from_raw(row);
+            sums.add(ind.age, ind.salary);
+            streamed += 1;
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-        let family_influence = match record[14].trim() {
-            "None" => Ok(0.0),
-            "Low" => Ok(1.0),
-            "Medium" => Ok(2.0),
-            "High" => Ok(3.0),
-            _ => Err("Invalid Family Influence value"),
+        let ages: Vec<f64> = individuals.iter().map(|ind| ind.age).collect();
+        let salaries: Vec<f64> = individuals.iter().map(|ind| ind.salary).collect();
+        let (slope, intercept, correlation, _) = calculate_linear_regression(&ages, &salaries);
+        let (s_slope, s_intercept, s_correlation, _) = sums.regression();
+
+        assert_eq!(streamed, 30);
+        assert!((slope - s_slope).abs() < 1e-9);
+        assert!((intercept - s_intercept).abs() < 1e-6);
+        assert!((correlation - s_correlation).abs() < 1e-12);
+
+        // A non-finite pair is skipped rather than poisoning the sums.
+        assert!(!sums.add_finite(f64::NAN, 50_000.0) && !sums.add_finite(30.0, f64::INFINITY));
+        assert!((sums.correlation() - s_correlation).abs() < 1e-12);
+    }
+
+    // Test the record cap limits how many rows are read
+    #[test]
+    fn test_read_dataset_max_records() {
+        let rows: Vec<String> = (0..5).map(|_| dataset_row("30", "5", "60000", "Low")).collect();
+        let path = write_temp_dataset("finalproject_test_max_records.csv", &rows);
+
+        let options = ReadOptions {
+            max_records: 3,
+            ..ReadOptions::default()
         };
+        let (individuals, _) = read_dataset(&[path.to_str().unwrap()], &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-        match (
-            record[2].trim().parse::<f64>(),     
-            record[4].trim().parse::<f64>(),     
-            record[7].trim().parse::<f64>(),    
-            record[19].trim().parse::<f64>(),    
-            family_influence,
-            record[10].trim().parse::<f64>(),    
-            record[22].trim().parse::<f64>(),    
-        ) {
-            (
-                Ok(age),
-                Ok(years_of_experience),
-                Ok(job_satisfaction),
-                Ok(professional_network_size),
-                Ok(family_influence),
-                Ok(salary),
-                Ok(likelihood_to_change_occupation),
-            ) => {
-                individuals.push(Individual {
-                    id: i,
-                    age,
-                    years_of_experience,
-                    job_satisfaction,
-                    professional_network_size,
-                    family_influence,
-                    salary,
-                    likelihood_to_change_occupation,
-                });
-            }
-            _ => {
-                parse_errors += 1;
-                eprintln!("Warning: Could not parse data for record {}", i);
-            }
-        }
+        assert_eq!(individuals.len(), 3);
+        assert_eq!(parse_args(&args(&["--max-records", "50000"])).unwrap().read_options.max_records, 50_000);
     }
 
-    println!("Total parse errors: {}", parse_errors);
-    Ok(individuals)
-}
+    // Test that rejected rows are reported with their index and reason
+    #[test]
+    fn test_read_dataset_parse_errors() {
+        let rows = vec![
+            dataset_row("30", "5", "60000", "Low"),
+            dataset_row("abc", "5", "60000", "Low"),
+            dataset_row("30", "5", "60000", "Extreme"),
+            "Medicine,Doctor,30".to_string(),
+        ];
+        let path = write_temp_dataset("finalproject_test_parse_errors.csv", &rows);
 
-fn calculate_linear_regression(x: &[f64], y: &[f64]) -> (f64, f64, f64, f64) {
-    assert_eq!(x.len(), y.len(), "Input vectors must be of equal length");
-    let n = x.len() as f64;
+        let (individuals, report) =
+            read_dataset(&[path.to_str().unwrap()], &ReadOptions::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-    let mean_x: f64 = x.iter().sum::<f64>() / n;
-    let mean_y: f64 = y.iter().sum::<f64>() / n;
+        assert_eq!(individuals.len(), 1);
+        let expected = vec![
+            ParseError {
+                record_index: 1,
+                reason: "could not parse Age".to_string(),
+            },
+            ParseError {
+                record_index: 2,
+                reason: "invalid Family Influence value 'Extreme'".to_string(),
+            },
+            ParseError {
+                record_index: 3,
+                reason: "short record (3 fields, expected at least 23)".to_string(),
+            },
+        ];
+        assert_eq!(report.parse_errors, expected);
 
-    let mut var_x = 0.0;
-    let mut cov_xy = 0.0;
+        // The short record is counted as read but not as per-column misses.
+        assert_eq!(report.records_read, 4);
+        assert_eq!(report.missing, [1, 0, 0, 0, 0, 0]);
+        assert_eq!(report.missing_family_influence, 1);
 
-    for i in 0..x.len() {
-        var_x += (x[i] - mean_x).powi(2);
-        cov_xy += (x[i] - mean_x) * (y[i] - mean_y);
+        // Three of four rows were rejected.
+        assert!(check_dataset(individuals.len(), &report, 0.8).is_ok());
+        assert!(matches!(
+            check_dataset(individuals.len(), &report, 0.5),
+            Err(AnalysisError::ParseError(_))
+        ));
+        assert_eq!(parse_args(&args(&["--check"])).unwrap().check, Some(DEFAULT_MAX_ERROR_RATE));
+        assert_eq!(parse_args(&args(&["--max-error-rate", "0.1", "--check"])).unwrap().check, Some(0.1));
+        assert!(parse_args(&args(&["--max-error-rate", "0.1"])).is_err());
     }
 
-    var_x /= n - 1.0; 
+    // Test a saved sample reads back with the same values and labels
+    #[test]
+    fn test_write_sample_csv_round_trip() {
+        let path = std::env::temp_dir().join("finalproject_test_sample.csv");
+        let path_str = path.to_str().unwrap();
+        let mut sample = sample_individuals(5);
+        sample[4].family_influence = 4.0;
+
+        write_sample_csv(path_str, &sample, b',').unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let (individuals, report) = read_dataset(&[path_str], &ReadOptions::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(written.starts_with("id,Age,Years of Experience,"));
+        assert!(written.lines().nth(1).unwrap().ends_with(",None"));
+        assert!(written.lines().nth(5).unwrap().ends_with(",Very High"));
+        assert!(report.parse_errors.is_empty());
+        assert_eq!(individuals.len(), 5);
+        for (read, original) in individuals.iter().zip(&sample) {
+            assert_eq!(read.salary, original.salary);
+            assert_eq!(read.family_influence, original.family_influence);
+            assert_eq!(read.likelihood_to_change_occupation, original.likelihood_to_change_occupation);
+        }
+        assert!(parse_args(&args(&["--stream", "--save-sample", "out.csv"])).is_err());
+        assert!(parse_args(&args(&["--stream", "--scale", "zscore"])).is_err());
+        assert!(parse_args(&args(&["--stream", "--clusters", "3"])).is_err());
+    }
 
-    let slope = cov_xy / (var_x * (n - 1.0));
-    let intercept = mean_y - slope * mean_x;
+    // Test the Markdown report has the statistics and per-analysis tables
+    #[test]
+    fn test_markdown_report() {
+        let sample = sample_individuals(8);
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &AnalysisOptions::default());
 
-    let mut r_numerator = 0.0;
-    let mut r_denomx = 0.0;
-    let mut r_denomy = 0.0;
+        let report = markdown_report(42, &sample, &results);
 
-    for i in 0..x.len() {
-        let dx = x[i] - mean_x;
-        let dy = y[i] - mean_y;
-        r_numerator += dx * dy;
-        r_denomx += dx.powi(2);
-        r_denomy += dy.powi(2);
+        assert!(report.starts_with("# Salary Analysis Report\n"));
+        assert!(report.contains("Random seed: 42, sample size: 8"));
+        assert!(report.contains("| Age | 8 | 23.50 |"));
+        assert!(report.contains("| Low | 25.00% |"));
+        assert!(report.contains("| Salary vs Years of Experience | 1000.0000 | 50000.0000 | 1.0000 |"));
+        assert_eq!(report.lines().filter(|line| line.starts_with("| Salary vs")).count(), 6);
     }
 
-    let correlation = r_numerator / (r_denomx * r_denomy).sqrt();
-    let r_squared = correlation.powi(2);
+    // Test the summary ranking is by absolute correlation with NaN last
+    #[test]
+    fn test_ranked_by_correlation() {
+        let sample = sample_individuals(8);
+        let mut results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &AnalysisOptions::default());
+        results[0].correlation = f64::NAN;
+        results[1].correlation = -0.9;
+        results[2].correlation = 0.5;
+        results[3].correlation = 0.95;
+        results[4].correlation = -0.1;
+        results[5].correlation = 0.3;
+
+        let ranked: Vec<f64> = ranked_by_correlation(&results).iter().map(|r| r.correlation).collect();
 
-    (slope, intercept, correlation, r_squared)
-}
+        assert_eq!(&ranked[..5], &[0.95, -0.9, 0.5, 0.3, -0.1]);
+        assert!(ranked[5].is_nan());
+    }
 
-fn perform_salary_correlation_analysis(individuals: &[Individual]) -> Result<(), Box<dyn Error>> {
-    let analyses = vec![
-        ("Salary vs Age",
-         individuals.iter().map(|ind| ind.age).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
+    // Test --auto-rank-ordinal switches only the ordinal analyses to Spearman
+    #[test]
+    fn test_auto_rank_ordinal() {
+        let sample = sample_individuals(8);
+        let config = parse_args(&args(&["--auto-rank-ordinal"])).unwrap();
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &config.analysis);
 
-        ("Salary vs Years of Experience",
-         individuals.iter().map(|ind| ind.years_of_experience).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
+        let methods: Vec<&str> = results.iter().map(|r| r.method()).collect();
+        assert_eq!(methods, vec!["Pearson", "Pearson", "Pearson", "Pearson", "Spearman", "Spearman"]);
+        assert_eq!(results[4].primary_correlation(), results[4].spearman);
 
-        ("Salary vs Job Satisfaction",
-         individuals.iter().map(|ind| ind.job_satisfaction).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
+        let by_target = parse_args(&args(&["--auto-rank-ordinal", "--target", "likelihood"])).unwrap();
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &by_target.analysis);
+        assert!(results.iter().all(|r| r.rank_based));
 
-        ("Salary vs Professional Network Size",
-         individuals.iter().map(|ind| ind.professional_network_size).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &AnalysisOptions::default());
+        assert!(results.iter().all(|r| !r.rank_based));
+    }
 
-        ("Salary vs Family Influence",
-         individuals.iter().map(|ind| ind.family_influence).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
+    // Test the log transform drops non-positive salaries and relabels results
+    #[test]
+    fn test_log_target() {
+        let mut individuals = sample_individuals(6);
+        individuals[0].salary = 0.0;
+        individuals[1].salary = -5.0;
 
-        ("Salary vs Likelihood to Change Occupation",
-         individuals.iter().map(|ind| ind.likelihood_to_change_occupation).collect::<Vec<f64>>(),
-         individuals.iter().map(|ind| ind.salary).collect::<Vec<f64>>()),
-    ];
+        let (logged, dropped) = log_transform_salary(individuals);
 
-    println!("\n--- Salary Correlation Analyses ---");
-    
-    for (title, x, y) in analyses {
-        let (slope, intercept, correlation, r_squared) = 
-            calculate_linear_regression(&x, &y);
+        assert_eq!(dropped, 2);
+        assert_eq!(logged.len(), 4);
+        assert!((logged[0].salary - 52_000.0_f64.ln()).abs() < 1e-12);
 
-        println!("\n{}:", title);
-        println!("Correlation Coefficient: {:.4}", correlation);
-        println!("Regression Equation: Salary = {:.4} * X + {:.4}", slope, intercept);
-        println!("R-squared: {:.4}", r_squared);
+        let config = parse_args(&args(&["--log-target", "--features", "age"])).unwrap();
+        let results = compute_correlations(&Dataset(logged.clone()), &Dataset::default(), &config.analysis);
+        assert_eq!(results[0].title, "log(Salary) vs Age");
+        assert_eq!(results[0].response(), "log(Salary)");
+    }
 
-        if correlation.abs() < 0.3 {
-            println!("Weak correlation");
-        } else if correlation.abs() < 0.7 {
-            println!("Moderate correlation");
-        } else {
-            println!("Strong correlation");
+    // Test the nested comparison finds a useful added predictor and parses
+    // its default base
+    #[test]
+    fn test_nested_model_comparison() {
+        let mut individuals = sample_individuals(12);
+        for ind in individuals.iter_mut() {
+            ind.job_satisfaction = (ind.id % 3) as f64;
+            ind.salary = 1_000.0 * ind.years_of_experience + 500.0 * ind.job_satisfaction + (ind.id % 2) as f64;
         }
+        let few = Dataset(individuals[..3].to_vec());
+        let sample = Dataset(individuals);
+        let (base, full, f, p_value) =
+            nested_model_comparison(&sample, &[Field::Experience], Field::Satisfaction).unwrap();
+        assert!(full > base && full > 0.999);
+        assert!(f > 100.0 && p_value < 1e-6);
+
+        // Three rows leave no residual degrees of freedom for two predictors,
+        // and age duplicates experience up to a shift.
+        assert!(nested_model_comparison(&few, &[Field::Experience], Field::Satisfaction).is_none());
+        assert!(nested_model_comparison(&sample, &[Field::Experience], Field::Age).is_none());
+
+        let config = parse_args(&args(&["--nested-add", "network"])).unwrap();
+        assert_eq!(config.nested_base.len(), 5);
+        assert!(!config.nested_base.contains(&Field::Network));
+        assert!(parse_args(&args(&["--nested-add", "age", "--nested-base", "age,network"])).is_err());
+        assert!(parse_args(&args(&["--nested-base", "age"])).is_err());
     }
 
-    Ok(())
-}
+    // Test filtering on a categorical field leaves its now-constant column out
+    // of the multiple regression instead of making the fit singular
+    #[test]
+    fn test_multiple_regression_after_categorical_filter() {
+        let mut individuals = sample_individuals(16);
+        for ind in individuals.iter_mut() {
+            ind.age = 20.0 + ((ind.id * 7) % 16) as f64;
+            ind.job_satisfaction = (ind.id % 3) as f64;
+        }
+        let filtered = apply_filters(individuals, &[Filter::parse("influence==2").unwrap()]);
+        assert_eq!(filtered.len(), 4);
 
-fn print_sample_verification(sample: &[Individual]) {
-    println!("\n--- Random Sample Verification ---");
-    println!("Total records in sample: {}", sample.len());
+        let mut features: Vec<(&str, Vec<f64>)> = vec![
+            ("Years of Experience", filtered.iter().map(|ind| ind.years_of_experience).collect()),
+            ("Family Influence", filtered.iter().map(|ind| ind.family_influence).collect()),
+            ("External", vec![f64::NAN, 1.0, f64::NAN, 1.0]),
+        ];
+        drop_constant_columns(&mut features);
+        let names: Vec<&str> = features.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["Years of Experience"]);
 
-    let ages: Vec<f64> = sample.iter().map(|ind| ind.age).collect();
-    let experiences: Vec<f64> = sample.iter().map(|ind| ind.years_of_experience).collect();
-    let salaries: Vec<f64> = sample.iter().map(|ind| ind.salary).collect();
-    
-    println!("\nAge Distribution:");
-    print_stats(&ages);
+        let salaries: Vec<f64> = filtered.iter().map(|ind| ind.salary).collect();
+        let columns: Vec<Vec<f64>> = features.into_iter().map(|(_, column)| column).collect();
+        let (coefficients, r_squared) = fit_multiple_regression(&columns, &salaries).unwrap();
+        assert!((coefficients[1] - 1000.0).abs() < 1e-6 && (r_squared - 1.0).abs() < 1e-9);
+        perform_multiple_regression(&filtered, true, Some(1.0), true, None);
+    }
 
-    println!("\nYears of Experience Distribution:");
-    print_stats(&experiences);
+    // Test --sort-by takes a deterministic head and splits it systematically
+    #[test]
+    fn test_sorted_head_and_systematic_split() {
+        let mut individuals = sample_individuals(6);
+        individuals.reverse();
+        individuals[0].family_influence = f64::NAN;
+        let head = sorted_head(&individuals, Field::Influence, 4);
+        let ids: Vec<usize> = head.iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0, 4, 1, 2]);
 
-    println!("\nSalary Distribution:");
-    print_stats(&salaries);
+        let (train, test) = systematic_split(&sample_individuals(10), 0.2);
+        let test_ids: Vec<usize> = test.iter().map(|ind| ind.id).collect();
+        assert_eq!(test_ids, vec![4, 9]);
+        assert_eq!(train.len(), 8);
 
-    println!("\nFamily Influence Distribution:");
-    let family_influence_counts: Vec<f64> = vec![
-        sample.iter().filter(|ind| ind.family_influence == 0.0).count() as f64,
-        sample.iter().filter(|ind| ind.family_influence == 1.0).count() as f64,
-        sample.iter().filter(|ind| ind.family_influence == 2.0).count() as f64,
-        sample.iter().filter(|ind| ind.family_influence == 3.0).count() as f64,
-    ];
-    println!("None: {:.2}%", family_influence_counts[0] / sample.len() as f64 * 100.0);
-    println!("Low: {:.2}%", family_influence_counts[1] / sample.len() as f64 * 100.0);
-    println!("Medium: {:.2}%", family_influence_counts[2] / sample.len() as f64 * 100.0);
-    println!("High: {:.2}%", family_influence_counts[3] / sample.len() as f64 * 100.0);
+        assert!(parse_args(&args(&["--sort-by", "age", "--stratify"])).is_err());
+        assert_eq!(parse_args(&args(&["--sort-by", "age"])).unwrap().sort_by, Some(Field::Age));
+        assert_eq!(parse_args(&args(&["--sort-by", "age"])).unwrap().seed, Some(SORTED_RUN_SEED));
+        assert_eq!(parse_args(&args(&["--sort-by", "age", "--seed", "5"])).unwrap().seed, Some(5));
+    }
 
-    println!("\nFirst 10 Records (Original IDs):");
-    for ind in sample.iter().take(10) {
-        println!("ID: {}, Age: {}, Salary: {}", ind.id, ind.age, ind.salary);
+    // Test two --sort-by runs without --seed write byte-identical output
+    #[test]
+    fn test_sorted_runs_are_identical() {
+        let rows: Vec<String> = (0..30)
+            .map(|i| dataset_row(&(20 + i % 13).to_string(), &(i % 7).to_string(), &(40_000 + i * 977 % 9_000).to_string(), "Low"))
+            .collect();
+        let path = write_temp_dataset("finalproject_test_sorted_runs.csv", &rows);
+        let outputs: Vec<(String, String)> = (0..2)
+            .map(|run_index| {
+                let report = std::env::temp_dir().join(format!("finalproject_test_sorted_report_{}.md", run_index));
+                let sample = std::env::temp_dir().join(format!("finalproject_test_sorted_sample_{}.csv", run_index));
+                let args: Vec<String> = [
+                    "correlate",
+                    path.to_str().unwrap(),
+                    "--sort-by",
+                    "salary",
+                    "--sample-size",
+                    "20",
+                    "--bootstrap",
+                    "50",
+                    "--report",
+                    report.to_str().unwrap(),
+                    "--save-sample",
+                    sample.to_str().unwrap(),
+                ]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+                run(&args).unwrap();
+                let written = (std::fs::read_to_string(&report).unwrap(), std::fs::read_to_string(&sample).unwrap());
+                std::fs::remove_file(&report).unwrap();
+                std::fs::remove_file(&sample).unwrap();
+                written
+            })
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(outputs[0].0.contains(&format!("Random seed: {}", SORTED_RUN_SEED)));
+        assert_eq!(outputs[0], outputs[1]);
     }
 
-    println!("\nLast 10 Records (Original IDs):");
-    for ind in sample.iter().rev().take(10) {
-        println!("ID: {}, Age: {}, Salary: {}", ind.id, ind.age, ind.salary);
+    // Test quantile grouping and the one-way ANOVA F statistic
+    // (groups [1,2,3], [4,5,6]: SSB = 13.5, SSW = 4, F = 13.5 / (4 / 4))
+    #[test]
+    fn test_anova_by_bins() {
+        let (f, p_value) = one_way_anova(&[vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert!((f - 13.5).abs() < 1e-12);
+        assert!(p_value > 0.02 && p_value < 0.025, "F(1, 4) = 13.5 has p near 0.0213");
+        assert!(one_way_anova(&[vec![1.0, 2.0]]).0.is_nan());
+
+        let groups = salary_by_quantile_bins(&sample_individuals(8), Field::Experience, 4);
+        let ranges: Vec<(f64, f64)> = groups.iter().map(|(range, _)| *range).collect();
+        assert_eq!(ranges, vec![(0.0, 1.0), (2.0, 3.0), (4.0, 5.0), (6.0, 7.0)]);
+        assert_eq!(groups[0].1, vec![50_000.0, 51_000.0]);
+
+        let tied = salary_by_quantile_bins(&sample_individuals(8), Field::Satisfaction, 4);
+        assert_eq!(tied.len(), 1);
+        assert!(parse_args(&args(&["--anova", "experience", "--anova-bins", "1"])).is_err());
     }
-}
 
-fn print_stats(data: &[f64]) {
-    let mean = data.iter().sum::<f64>() / data.len() as f64;
-    let min = data.iter().cloned().fold(f64::INFINITY, |a, b| a.min(b));
-    let max = data.iter().cloned().fold(f64::NEG_INFINITY, |a, b| a.max(b));
-    
-    println!("Mean: {:.2}", mean);
-    println!("Min: {:.2}", min);
-    println!("Max: {:.2}", max);
-}
+    // Test REPL commands parse, and that bad input doesn't end the loop
+    #[test]
+    fn test_repl() {
+        assert_eq!(ReplCommand::parse("stats salary"), Ok(ReplCommand::Stats(Field::Salary)));
+        assert_eq!(ReplCommand::parse(" corr age  salary "), Ok(ReplCommand::Corr(Field::Age, Field::Salary)));
+        assert_eq!(ReplCommand::parse("predict experience 7"), Ok(ReplCommand::Predict(Field::Experience, 7.0)));
+        assert_eq!(ReplCommand::parse("exit"), Ok(ReplCommand::Quit));
+        assert!(ReplCommand::parse("predict experience seven").is_err());
+        assert!(ReplCommand::parse("stats height").is_err());
+        assert!(ReplCommand::parse("plot salary").is_err());
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let file_path = "career_dataset.csv";
-    let mut individuals = read_dataset(file_path)?;
+        let input = io::Cursor::new("bogus\n\nstats age\ncorr age salary\npredict experience 2\nquit\nstats salary\n");
+        run_repl(&Dataset(sample_individuals(5)), &AnalysisOptions::default(), input).unwrap();
+        assert!(parse_args(&args(&["--repl", "--json"])).is_err());
+        assert!(parse_args(&args(&["-", "--repl"])).is_err());
 
-    if individuals.is_empty() {
-        eprintln!("No individuals loaded from the dataset!");
-        return Ok(());
+        let mut sample = sample_individuals(5);
+        sample[1].salary = f64::NAN;
+        sample[3].age = f64::INFINITY;
+        let (ages, salaries) = Dataset(sample).finite_pair_columns(Field::Age, Field::Salary);
+        assert_eq!(ages, vec![20.0, 22.0, 24.0]);
+        assert_eq!(salaries, vec![50_000.0, 52_000.0, 54_000.0]);
     }
 
-    let mut rng = thread_rng();
-    individuals.shuffle(&mut rng);
+    // Test winsorizing clamps both tails to the percentiles and keeps NaN
+    #[test]
+    fn test_winsorize() {
+        let data = [1.0, 2.0, 3.0, 4.0, 100.0, f64::NAN];
+        let capped = winsorize(&data, 0.25, 0.75);
+        assert_eq!(&capped[..5], &[2.0, 2.0, 3.0, 4.0, 4.0]);
+        assert!(capped[5].is_nan());
+        assert_eq!(winsorize(&data[..5], 0.0, 1.0), data[..5].to_vec());
 
-    let final_sample: Vec<Individual> = individuals.into_iter().take(2_000).collect();
+        let mut individuals = sample_individuals(5);
+        individuals[4].salary = 1e9;
+        let (capped, raised, lowered) = winsorize_salaries(individuals, 0.25);
+        assert_eq!((raised, lowered), (1, 1));
+        assert_eq!(capped.len(), 5);
+        assert_eq!(capped[4].salary, 53_000.0);
+        assert!(parse_args(&args(&["--winsorize", "0.05", "--trim-outliers"])).is_err());
+    }
 
-    print_sample_verification(&final_sample);
+    // Test the three means coincide for a constant vector and order as
+    // AM >= GM >= HM otherwise
+    #[test]
+    fn test_geometric_and_harmonic_mean() {
+        let constant = [4.0; 5];
+        let stats = compute_stats(&constant);
+        assert!((geometric_mean(&constant) - stats.mean).abs() < 1e-12);
+        assert!((harmonic_mean(&constant) - stats.mean).abs() < 1e-12);
 
-    perform_salary_correlation_analysis(&final_sample)?;
+        let data = [1.0, 2.0, 4.0];
+        assert!((geometric_mean(&data) - 2.0).abs() < 1e-12);
+        assert!((harmonic_mean(&data) - 12.0 / 7.0).abs() < 1e-12);
 
-    Ok(())
-}
+        assert!(geometric_mean(&[1.0, 0.0]).is_nan());
+        assert!(harmonic_mean(&[1.0, -2.0]).is_nan());
+        assert!(geometric_mean(&[]).is_nan());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Cursor;
+    // Test the centered moving average shrinks at the edges and that the
+    // salary trend groups by distinct age
+    #[test]
+    fn test_moving_average() {
+        let data = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(moving_average(&data, 1), data.to_vec());
+        assert_eq!(moving_average(&data, 3), vec![1.5, 2.0, 3.0, 4.0, 4.5]);
+        assert_eq!(moving_average(&data, 2), vec![1.5, 2.5, 3.5, 4.5, 5.0]);
+        assert!(moving_average(&[], 3).is_empty());
 
-    // Test linear regression with a simple known dataset
+        let mut sample = sample_individuals(3);
+        sample[2].age = 20.0;
+        let trend = salary_trend_by_age(&sample, 2);
+        assert_eq!(trend, vec![(20.0, 51_000.0, 51_000.0), (21.0, 51_000.0, 51_000.0)]);
+    }
+
+    // Test the pipeline accounting totals reconcile stage by stage
     #[test]
-    fn test_calculate_linear_regression() {
-        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let y = vec![2.0, 4.0, 6.0, 8.0, 10.0];
-        
-        let (slope, intercept, correlation, r_squared) = 
-            calculate_linear_regression(&x, &y);
+    fn test_pipeline_accounting() {
+        let report = ReadReport {
+            parse_errors: (0..3)
+                .map(|i| ParseError { record_index: i, reason: String::new() })
+                .collect(),
+            skipped: 7,
+            out_of_range_rows: 2,
+            records_read: 100,
+            short_records: 1,
+            ..ReadReport::default()
+        };
+        let counts = PipelineCounts { duplicates: 4, unjoined: 0, filtered: 11, sampled: 50, trimmed: 3, log_dropped: 0 };
 
-        assert!((slope - 2.0).abs() < 1e-6, "Slope should be 2");
-        assert!((intercept - 0.0).abs() < 1e-6, "Intercept should be 0");
-        assert!((correlation - 1.0).abs() < 1e-6, "Correlation should be 1");
-        assert!((r_squared - 1.0).abs() < 1e-6, "R-squared should be 1");
+        let rows: HashMap<&str, usize> = pipeline_accounting(&report, &counts).into_iter().collect();
+        assert_eq!(rows["Dropped as short records"], 1);
+        assert_eq!(rows["Dropped for parse errors"], 2);
+        assert_eq!(rows["Rows loaded"], 95);
+        assert_eq!(rows["Rows available for sampling"], 80);
+        assert_eq!(rows["Rows used in analysis"], 47);
+
+        // Salaries --log-target can't take the log of are not used either.
+        let mut sample = sample_individuals(5);
+        sample[2].salary = 0.0;
+        let (logged, log_dropped) = log_transform_salary(sample);
+        let counts = PipelineCounts { sampled: 5, log_dropped, ..PipelineCounts::default() };
+        let rows: HashMap<&str, usize> = pipeline_accounting(&report, &counts).into_iter().collect();
+        assert_eq!(rows["Dropped by log transform"], 1);
+        assert_eq!(rows["Rows used in analysis"], logged.len());
     }
 
-    // Test error handling in linear regression
+    // Test influence dummies skip absent levels, drop the lowest present one
+    // as the reference and keep missing values missing
     #[test]
-    #[should_panic(expected = "Input vectors must be of equal length")]
-    fn test_linear_regression_different_lengths() {
-        let x = vec![1.0, 2.0, 3.0];
-        let y = vec![1.0, 2.0, 3.0, 4.0];
-        
-        calculate_linear_regression(&x, &y);
+    fn test_influence_dummies() {
+        let mut individuals = sample_individuals(4);
+        individuals[0].family_influence = 1.0;
+        individuals[3].family_influence = f64::NAN;
+
+        let (reference, dummies) = influence_dummies(&individuals);
+        assert_eq!(reference, "Low");
+        let levels: Vec<&str> = dummies.iter().map(|(level, _)| *level).collect();
+        assert_eq!(levels, vec!["Medium"]);
+        assert_eq!(&dummies[0].1[..3], &[0.0, 0.0, 1.0]);
+        assert!(dummies[0].1[3].is_nan());
+        assert!(influence_dummies(&[]).1.is_empty());
     }
 
-    // Test Individual struct creation
+    // Test rolling windows slide over the age-sorted sample and report NaN
+    // where a window has no variation
     #[test]
-    fn test_individual_creation() {
-        let individual = Individual {
-            id: 1,
-            age: 30.0,
-            years_of_experience: 5.0,
-            job_satisfaction: 4.5,
-            professional_network_size: 100.0,
-            family_influence: 2.0,
-            salary: 75000.0,
-            likelihood_to_change_occupation: 0.3,
+    fn test_rolling_correlation() {
+        let mut individuals = sample_individuals(6);
+        individuals.reverse();
+        let series = rolling_correlation(&individuals, Field::Age, Field::Experience, Field::Salary, 4, 2);
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].0, 21.5);
+        assert_eq!(series[1].0, 23.5);
+        assert!(series.iter().all(|&(_, r)| (r - 1.0).abs() < 1e-12));
+
+        let flat = rolling_correlation(&individuals, Field::Age, Field::Satisfaction, Field::Salary, 3, 1);
+        assert_eq!(flat.len(), 4);
+        assert!(flat.iter().all(|&(_, r)| r.is_nan()));
+        assert!(rolling_correlation(&individuals, Field::Age, Field::Experience, Field::Salary, 7, 1).is_empty());
+        assert!(parse_args(&args(&["--rolling-window", "2"])).is_err());
+        assert!(parse_args(&args(&["--rolling-step", "5"])).is_err());
+    }
+
+    // Test colliding ids are found once each and renumbering clears them
+    #[test]
+    fn test_duplicate_ids() {
+        let mut individuals = sample_individuals(5);
+        assert!(duplicate_ids(&individuals).is_empty());
+
+        individuals[1].id = 4;
+        individuals[2].id = 0;
+        individuals[3].id = 4;
+        assert_eq!(duplicate_ids(&individuals), vec![0, 4]);
+
+        reassign_ids(&mut individuals);
+        let ids: Vec<usize> = individuals.iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+        assert!(parse_args(&args(&["--reassign-ids"])).unwrap().reassign_ids);
+    }
+
+    // Test that duplicates are removed, keeping the first occurrence's id
+    #[test]
+    fn test_dedup_individuals() {
+        let mut individuals = sample_individuals(4);
+        let mut copy = individuals[1].clone();
+        copy.id = 10;
+        copy.salary += 1e-12;
+        individuals.push(copy);
+        individuals.push(individuals[3].clone());
+
+        let deduped = dedup_individuals(individuals);
+
+        let ids: Vec<usize> = deduped.iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        assert!(parse_args(&args(&["--dedup", "--stream"])).is_err());
+
+        // NaN matches nothing, not even another NaN or 0.0, and large values
+        // far more than epsilon apart stay distinct.
+        let mut individuals = sample_individuals(2);
+        individuals[0].salary = 0.0;
+        individuals[1].salary = f64::NAN;
+        individuals.push(individuals[1].clone());
+        for (id, salary) in [(3, 1e10), (4, 1e10 + 1.0), (5, 2e19), (6, 3e19)] {
+            let mut ind = individuals[0].clone();
+            ind.id = id;
+            ind.salary = salary;
+            individuals.push(ind);
+        }
+        let ids: Vec<usize> = dedup_individuals(individuals).iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0, 1, 1, 3, 4, 5, 6]);
+
+        // Values within epsilon across a rounding boundary still match, in
+        // salary and in the other fields.
+        let mut individuals = sample_individuals(1);
+        for (id, value) in [(1, 1.2e-9), (2, 1.6e-9)] {
+            let mut ind = individuals[0].clone();
+            ind.id = id;
+            ind.likelihood_to_change_occupation = value;
+            ind.salary += value;
+            individuals.push(ind);
+        }
+        individuals[0].likelihood_to_change_occupation = 1.4e-9;
+        individuals[0].salary += 1.4e-9;
+        let ids: Vec<usize> = dedup_individuals(individuals).iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0]);
+    }
+
+    // Test that grouped numbers like "85,000" are only accepted on request
+    #[test]
+    fn test_read_dataset_thousands_separators() {
+        let rows = vec![
+            dataset_row("30", "5", "\"85,000\"", "Low"),
+            dataset_row("40", "5", "\"1,250,000.50\"", "Low"),
+        ];
+        let path = write_temp_dataset("finalproject_test_thousands.csv", &rows);
+        let path_str = path.to_str().unwrap();
+
+        let (individuals, report) = read_dataset(&[path_str], &ReadOptions::default()).unwrap();
+        assert!(individuals.is_empty());
+        assert_eq!(report.parse_errors.len(), 2);
+
+        let config = parse_args(&args(&["--thousands-separators"])).unwrap();
+        let (individuals, _) = read_dataset(&[path_str], &config.read_options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(individuals.len(), 2);
+        assert_eq!(individuals[0].salary, 85_000.0);
+        assert_eq!(individuals[1].salary, 1_250_000.5);
+        assert_eq!(parse_numeric(" \"2,500\" ", true), Some(2_500.0));
+        assert_eq!(parse_numeric("2,500", false), None);
+    }
+
+    // Test several files concatenate with unique ids and a combined record cap
+    #[test]
+    fn test_read_dataset_multiple_files() {
+        let first = write_temp_dataset(
+            "finalproject_test_multi_1.csv",
+            &[dataset_row("30", "5", "60000", "Low"), dataset_row("x", "5", "60000", "Low")],
+        );
+        let second = write_temp_dataset(
+            "finalproject_test_multi_2.csv",
+            &[dataset_row("40", "9", "70000", "High"), dataset_row("50", "9", "80000", "High")],
+        );
+        let paths = [first.to_str().unwrap(), second.to_str().unwrap()];
+
+        let (individuals, report) = read_dataset(&paths, &ReadOptions::default()).unwrap();
+        let ids: Vec<usize> = individuals.iter().map(|ind| ind.id).collect();
+        assert_eq!(ids, vec![0, 2, 3]);
+        assert_eq!(report.parse_errors.len(), 1);
+        assert_eq!(report.parse_errors[0].record_index, 1);
+
+        let capped = ReadOptions {
+            max_records: 3,
+            ..ReadOptions::default()
         };
+        let (individuals, report) = read_dataset(&paths, &capped).unwrap();
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+        assert_eq!(individuals.len(), 2);
+        assert_eq!(report.skipped, 1);
 
-        assert_eq!(individual.id, 1);
-        assert_eq!(individual.age, 30.0);
-        assert_eq!(individual.family_influence, 2.0);
+        let config = parse_args(&args(&["a.csv", "b.csv"])).unwrap();
+        assert_eq!(config.file_paths, vec!["a.csv", "b.csv"]);
+        assert!(parse_args(&args(&["a.csv", "b.csv", "--stream"])).is_err());
     }
 
-    // Test family influence parsing
+    // Test that implausible values drop the row and are counted per field
     #[test]
-    fn test_family_influence_parsing() {
-        let test_cases = vec![
-            ("None", 0.0),
-            ("Low", 1.0),
-            ("Medium", 2.0),
-            ("High", 3.0),
+    fn test_read_dataset_out_of_range() {
+        let rows = vec![
+            dataset_row("30", "5", "60000", "Low"),
+            dataset_row("500", "5", "60000", "Low"),
+            dataset_row("30", "-2", "-100", "Low"),
         ];
+        let path = write_temp_dataset("finalproject_test_out_of_range.csv", &rows);
 
-        for (input, expected) in test_cases {
-            let result = match input {
-                "None" => 0.0,
-                "Low" => 1.0,
-                "Medium" => 2.0,
-                "High" => 3.0,
-                _ => f64::NAN,
-            };
-            assert_eq!(result, expected);
-        }
+        let (individuals, report) =
+            read_dataset(&[path.to_str().unwrap()], &ReadOptions::default()).unwrap();
+
+        assert_eq!(individuals.len(), 1);
+        assert!(report.parse_errors.is_empty());
+        assert_eq!(report.out_of_range_rows, 2);
+        assert_eq!(report.out_of_range, [1, 1, 0, 0, 1, 0]);
+
+        // dataset_row uses a satisfaction of 7, outside a 1-5 scale.
+        let config = parse_args(&args(&["--satisfaction-range", "1,5"])).unwrap();
+        let (individuals, report) = read_dataset(&[path.to_str().unwrap()], &config.read_options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(individuals.is_empty());
+        assert_eq!(report.out_of_range[2], 3);
+        assert!(parse_args(&args(&["--satisfaction-range", "5,1"])).is_err());
+
+        // NaN and infinities are parse errors, not values in range.
+        let rows = vec![
+            dataset_row("NaN", "5", "60000", "Low"),
+            dataset_row("30", "inf", "60000", "Low"),
+            dataset_row("30", "5", "-inf", "Low"),
+            dataset_row("30", "5", "60000", "Low"),
+        ];
+        let path = write_temp_dataset("finalproject_test_non_finite.csv", &rows);
+        let (individuals, report) =
+            read_dataset(&[path.to_str().unwrap()], &ReadOptions::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(individuals.len(), 1);
+        assert_eq!(report.parse_errors.len(), 3);
+        assert_eq!(report.out_of_range_rows, 0);
+        assert_eq!(parse_numeric(" NaN ", false), None);
     }
 
-    // Test reading a small CSV dataset
+    // Test the --toml document parses back into the same report
+    #[cfg(feature = "toml")]
     #[test]
-    fn test_read_small_dataset() {
-        let csv_data = 
-"Age,Experience,Job Satisfaction,Network Size,Family Influence,Salary,Likelihood to Change
-30,5,4.5,100,Medium,75000,0.3
-35,10,4.8,200,High,85000,0.2
-25,2,3.9,50,Low,60000,0.5";
+    fn test_results_to_toml() {
+        let sample = sample_individuals(8);
+        let results = compute_correlations(&Dataset(sample.clone()), &Dataset::default(), &AnalysisOptions::default());
+        let text = results_to_toml(42, &sample, &results);
 
-        let cursor = Cursor::new(csv_data.as_bytes());
-        
-        let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(cursor);
+        let report: TomlReport = toml::from_str(&text).unwrap();
+        assert_eq!(report.seed, 42);
+        assert_eq!(report.analyses.len(), results.len());
+        assert_eq!(report.analyses[0].title, "Salary vs Age");
+        assert!(report.analyses[0].test_r_squared.is_none());
+        assert_eq!(toml::to_string(&report).unwrap(), text);
+        assert!(parse_args(&args(&["--toml", "--json"])).is_err());
+    }
 
-        let individuals: Vec<Individual> = rdr
-            .records()
-            .enumerate()
-            .filter_map(|(i, result)| {
-                let record = result.ok()?;
-                
-                let age = record.get(0)?.trim().parse::<f64>().ok()?;
-                let years_of_experience = record.get(1)?.trim().parse::<f64>().ok()?;
-                let job_satisfaction = record.get(2)?.trim().parse::<f64>().ok()?;
-                let professional_network_size = record.get(3)?.trim().parse::<f64>().ok()?;
-                
-                let family_influence = match record.get(4)?.trim() {
-                    "None" => Some(0.0),
-                    "Low" => Some(1.0),
-                    "Medium" => Some(2.0),
-                    "High" => Some(3.0),
-                    _ => None,
-                }?;
-                
-                let salary = record.get(5)?.trim().parse::<f64>().ok()?;
-                let likelihood_to_change_occupation = record.get(6)?.trim().parse::<f64>().ok()?;
+    // Test reading a gzip-compressed dataset
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_read_dataset_gzip() {
+        use std::io::Write;
 
-                Some(Individual {
-                    id: i,
-                    age,
-                    years_of_experience,
-                    job_satisfaction,
-                    professional_network_size,
-                    family_influence,
-                    salary,
-                    likelihood_to_change_occupation,
-                })
+        let path = std::env::temp_dir().join("finalproject_test_gzip.csv.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        write!(encoder, "{}\n{}", TEST_HEADER, dataset_row("30", "5", "60000", "Low")).unwrap();
+        encoder.finish().unwrap();
+
+        let (individuals, _) = read_dataset(&[path.to_str().unwrap()], &ReadOptions::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(individuals.len(), 1);
+        assert_eq!(individuals[0].salary, 60000.0);
+    }
+
+    // Test column resolution by header name, with fallback for missing headers
+    #[test]
+    fn test_column_config_from_headers() {
+        let headers: Vec<&str> = TEST_HEADER.split(',').collect();
+        let config = ColumnConfig::from_headers(&csv::StringRecord::from(headers.clone()));
+        assert_eq!(config, ColumnConfig::default());
+
+        let mut reordered = headers.clone();
+        reordered.swap(2, 10); // Age <-> Salary
+        let config = ColumnConfig::from_headers(&csv::StringRecord::from(reordered));
+        assert_eq!(config.age, 10);
+        assert_eq!(config.salary, 2);
+
+        let missing: Vec<&str> = headers
+            .iter()
+            .map(|h| if *h == "Job Satisfaction" { "Satisfaction" } else { h })
+            .collect();
+        let config = ColumnConfig::from_headers(&csv::StringRecord::from(missing));
+        assert_eq!(config.job_satisfaction, 7, "Missing header falls back to default index");
+        assert_eq!(config.required_len(), 23);
+    }
+
+    // Test read failures surface as distinct AnalysisError kinds
+    #[test]
+    fn test_analysis_error_kinds() {
+        let missing = std::env::temp_dir().join("finalproject_test_does_not_exist.csv");
+        let err = read_dataset(&[missing.to_str().unwrap()], &ReadOptions::default()).unwrap_err();
+        assert!(matches!(err, AnalysisError::IoError(_)));
+        assert_eq!(err.exit_code(), 3);
+
+        assert_eq!(AnalysisError::EmptyDataset.to_string(), "No individuals loaded from the dataset!");
+        assert_ne!(AnalysisError::EmptyDataset.exit_code(), 0);
+        assert_eq!(AnalysisError::InvalidArgument("bad".to_string()).exit_code(), 2);
+    }
+
+    // Test header validation lists every missing column before any rows are read
+    #[test]
+    fn test_validate_headers() {
+        let headers: Vec<&str> = TEST_HEADER.split(',').collect();
+        assert!(ColumnConfig::validate_headers(&csv::StringRecord::from(headers.clone()), true).is_ok());
+
+        let renamed: Vec<&str> = headers
+            .iter()
+            .map(|h| match *h {
+                "Job Satisfaction" => "Satisfaction",
+                "Salary" => "Pay",
+                other => other,
             })
             .collect();
+        let err = ColumnConfig::validate_headers(&csv::StringRecord::from(renamed.clone()), false).unwrap_err();
+        assert!(err.contains("'Job Satisfaction' missing (column 7 is 'Satisfaction')"));
+        assert!(err.contains("'Salary' missing (column 10 is 'Pay')"));
 
-        assert_eq!(individuals.len(), 3, "Should parse 3 records");
-        assert_eq!(individuals[0].age, 30.0);
-        assert_eq!(individuals[1].salary, 85000.0);
-        assert_eq!(individuals[2].family_influence, 1.0);
+        // Moved columns resolve by name unless --limit-columns pins them.
+        let mut reordered = headers.clone();
+        reordered.swap(2, 10);
+        let reordered = csv::StringRecord::from(reordered);
+        assert!(ColumnConfig::validate_headers(&reordered, false).is_ok());
+        let err = ColumnConfig::validate_headers(&reordered, true).unwrap_err();
+        assert!(err.contains("'Age' is column 10, expected column 2"));
+        assert!(err.contains("'Salary' is column 2, expected column 10"));
+        let config = parse_args(&args(&["--limit-columns"])).unwrap();
+        assert!(config.read_options.fixed_column_positions);
+        assert!(parse_args(&args(&["--limit-columns", "--lenient-columns"])).is_err());
+
+        let path = std::env::temp_dir().join("finalproject_test_headers.csv");
+        let path_str = path.to_str().unwrap();
+        let row = dataset_row("30", "5", "60000", "Low");
+        std::fs::write(&path, format!("{}\n{}", renamed.join(","), row)).unwrap();
+        assert!(matches!(
+            read_dataset(&[path_str], &ReadOptions::default()),
+            Err(AnalysisError::ParseError(_))
+        ));
+        let lenient = ReadOptions {
+            lenient_columns: true,
+            ..ReadOptions::default()
+        };
+        let (individuals, _) = read_dataset(&[path_str], &lenient).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(individuals.len(), 1);
     }
 
-    // Test print_stats function
+    // Test percentile interpolation, including the even-length median
     #[test]
-    fn test_print_stats() {
-        let test_data = vec![10.0, 20.0, 30.0, 40.0, 50.0];
-        
-        let mean = test_data.iter().sum::<f64>() / test_data.len() as f64;
-        let min = test_data.iter().cloned().fold(f64::INFINITY, |a, b| a.min(b));
-        let max = test_data.iter().cloned().fold(f64::NEG_INFINITY, |a, b| a.max(b));
-        
-        assert!((mean - 30.0).abs() < 1e-6, "Mean should be 30");
-        assert!((min - 10.0).abs() < 1e-6, "Min should be 10");
-        assert!((max - 50.0).abs() < 1e-6, "Max should be 50");
+    fn test_percentile() {
+        let odd = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!((percentile(&odd, 0.5) - 3.0).abs() < 1e-6, "Median should be 3");
+        assert!((percentile(&odd, 0.25) - 2.0).abs() < 1e-6, "Q1 should be 2");
+        assert!((percentile(&odd, 0.75) - 4.0).abs() < 1e-6, "Q3 should be 4");
+
+        let even = [1.0, 2.0, 3.0, 4.0];
+        assert!((percentile(&even, 0.5) - 2.5).abs() < 1e-6, "Median should be 2.5");
+        assert!((percentile(&even, 0.25) - 1.75).abs() < 1e-6, "Q1 should be 1.75");
+        assert!((percentile(&even, 0.75) - 3.25).abs() < 1e-6, "Q3 should be 3.25");
     }
 }